@@ -2,17 +2,21 @@ use crate::{
     config,
     daemon_response::DaemonResponseSender,
     display_backend, error_handling_ctx,
-    gtk::prelude::{ContainerExt, CssProviderExt, GtkWindowExt, StyleContextExt, WidgetExt},
+    gtk::prelude::{Cast, ContainerExt, CssProviderExt, GtkWindowExt, StyleContextExt, WidgetExt},
     script_var_handler::ScriptVarHandlerHandle,
-    state::scope_graph::{ScopeGraph, ScopeGraphEvent, ScopeIndex},
+    state::{
+        scope::Listener,
+        scope_graph::{ScopeGraph, ScopeGraphEvent, ScopeIndex},
+        temporary_overrides::TemporaryOverrides,
+    },
     EwwPaths, *,
 };
 use anyhow::anyhow;
 use eww_shared_util::VarName;
 use itertools::Itertools;
-use simplexpr::dynval::DynVal;
+use simplexpr::{dynval::DynVal, SimplExpr};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{HashMap, HashSet},
     rc::Rc,
 };
@@ -20,16 +24,41 @@ use tokio::sync::mpsc::UnboundedSender;
 use yuck::{
     config::{
         script_var_definition::ScriptVarDefinition,
-        window_definition::WindowDefinition,
+        window_definition::{WindowCloseAnimation, WindowCloseTransition, WindowDefinition},
         window_geometry::{AnchorPoint, WindowGeometry},
     },
-    value::Coords,
+    value::{Coords, NumWithUnit},
 };
 
+/// How long the monitor of a window has to stay unchanged before the window is moved to it.
+const MONITOR_CHANGE_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Interval in which the frames of a window's close animation are drawn.
+const CLOSE_ANIMATION_FRAME_INTERVAL: std::time::Duration = std::time::Duration::from_millis(16);
+
 #[derive(Debug)]
 pub enum DaemonCommand {
     NoOp,
     UpdateVars(Vec<(VarName, DynVal)>),
+    /// Update the given variables, reverting them to their previous values once the duration has passed.
+    UpdateVarsTemporarily {
+        mappings: Vec<(VarName, DynVal)>,
+        duration: std::time::Duration,
+    },
+    /// Revert a temporarily updated variable, if the revert with the given generation is still the latest one.
+    RevertTemporaryVar {
+        name: VarName,
+        generation: u64,
+    },
+    /// Remove the scope of a window once its close animation has finished and the window has been destroyed.
+    RemoveClosedWindowScope {
+        scope_index: ScopeIndex,
+    },
+    /// Set or clear the error of the script producing the given variable, exposed via `EWW_ERRORS`.
+    SetScriptVarError {
+        name: VarName,
+        error: Option<String>,
+    },
     ReloadConfigAndCss(DaemonResponseSender),
     UpdateConfig(config::EwwConfig),
     UpdateCss(String),
@@ -52,6 +81,15 @@ pub enum DaemonCommand {
         windows: Vec<String>,
         sender: DaemonResponseSender,
     },
+    RaiseWindow {
+        window_name: String,
+        sender: DaemonResponseSender,
+    },
+    /// Move a window to a different monitor after the value of its monitor expression changed.
+    MoveWindowToMonitor {
+        window_name: String,
+        monitor: i32,
+    },
     KillServer,
     CloseAll,
     PrintState {
@@ -62,9 +100,35 @@ pub enum DaemonCommand {
         name: String,
         sender: DaemonResponseSender,
     },
+    /// Stop the scripts of the given variables, and don't start them again until they are resumed.
+    PauseVars {
+        names: Vec<VarName>,
+        sender: DaemonResponseSender,
+    },
+    /// Resume the scripts of the given paused variables, starting them if they are in use.
+    ResumeVars {
+        names: Vec<VarName>,
+        sender: DaemonResponseSender,
+    },
+    /// Change the log level of the daemon if a level is given, and respond with the current log level.
+    SetLogLevel {
+        level: Option<String>,
+        sender: DaemonResponseSender,
+    },
     PrintDebug(DaemonResponseSender),
     PrintGraph(DaemonResponseSender),
+    PrintWidgetArgs {
+        window_name: String,
+        widget: String,
+        sender: DaemonResponseSender,
+    },
     PrintWindows(DaemonResponseSender),
+    /// Respond with the position and size of the widget with the given `:id` in an open window, in screen coordinates.
+    GetWidgetGeometry {
+        window_name: String,
+        widget_id: String,
+        sender: DaemonResponseSender,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -73,12 +137,48 @@ pub struct EwwWindow {
     pub definition: yuck::config::window_definition::WindowDefinition,
     pub scope_index: ScopeIndex,
     pub gtk_window: gtk::Window,
+    /// Geometry of the monitor the window is currently shown on.
+    pub monitor_geometry: Rc<Cell<gdk::Rectangle>>,
+    /// Handler terminating the commands of the widgets once the window is unmapped.
+    pub unmap_handler: Rc<glib::SignalHandlerId>,
 }
 
 impl EwwWindow {
     pub fn close(self) {
         self.gtk_window.close();
     }
+
+    /// Play the given close animation, closing the window once it has finished.
+    /// `on_closed` is called after the window has been closed.
+    pub fn close_animated(self, animation: WindowCloseAnimation, on_closed: impl FnOnce() + 'static) {
+        match animation.transition {
+            WindowCloseTransition::None => {
+                self.close();
+                on_closed();
+            }
+            WindowCloseTransition::Fade => {
+                let gtk_window = self.gtk_window;
+                let start = std::time::Instant::now();
+                let mut on_closed = Some(on_closed);
+                glib::timeout_add_local(CLOSE_ANIMATION_FRAME_INTERVAL, move || {
+                    let t = if animation.duration.is_zero() {
+                        1f64
+                    } else {
+                        (start.elapsed().as_secs_f64() / animation.duration.as_secs_f64()).min(1f64)
+                    };
+                    gtk_window.set_opacity(1f64 - t);
+                    if t < 1f64 {
+                        return glib::Continue(true);
+                    }
+                    gtk_window.close();
+                    if let Some(on_closed) = on_closed.take() {
+                        on_closed();
+                    }
+                    glib::Continue(false)
+                });
+            }
+        }
+    }
 }
 
 pub struct App {
@@ -88,6 +188,12 @@ pub struct App {
     /// Window names that are supposed to be open, but failed.
     /// When reloading the config, these should be opened again.
     pub failed_windows: HashSet<String>,
+    /// Variables that have been updated temporarily via `eww update --for`.
+    pub temporary_var_overrides: TemporaryOverrides,
+    /// The latest error of each script var whose script is currently failing, exposed via `EWW_ERRORS`.
+    pub script_var_errors: HashMap<VarName, String>,
+    /// Script vars that have been paused via `eww pause-var`, and should not be started until they are resumed.
+    pub paused_vars: HashSet<VarName>,
     pub css_provider: gtk::CssProvider,
 
     pub app_evt_send: UnboundedSender<DaemonCommand>,
@@ -123,6 +229,28 @@ impl App {
                         self.update_global_state(var_name, new_value);
                     }
                 }
+                DaemonCommand::UpdateVarsTemporarily { mappings, duration } => {
+                    for (var_name, new_value) in mappings {
+                        self.update_global_state_temporarily(var_name, new_value, duration)?;
+                    }
+                }
+                DaemonCommand::RevertTemporaryVar { name, generation } => {
+                    if let Some(original_value) = self.temporary_var_overrides.take_revert(&name, generation) {
+                        self.set_global_value(name, original_value);
+                    }
+                }
+                DaemonCommand::RemoveClosedWindowScope { scope_index } => {
+                    self.remove_closed_window_scope(scope_index);
+                }
+                DaemonCommand::SetScriptVarError { name, error } => {
+                    let changed = match error {
+                        Some(error) => self.script_var_errors.insert(name, error.clone()).as_ref() != Some(&error),
+                        None => self.script_var_errors.remove(&name).is_some(),
+                    };
+                    if changed && self.scope_graph.borrow().currently_used_globals().contains(&VarName::from("EWW_ERRORS")) {
+                        self.publish_script_var_errors()?;
+                    }
+                }
                 DaemonCommand::ReloadConfigAndCss(sender) => {
                     let mut errors = Vec::new();
 
@@ -185,6 +313,17 @@ impl App {
                     let errors = windows.iter().map(|window| self.close_window(window)).filter_map(Result::err);
                     sender.respond_with_error_list(errors)?;
                 }
+                DaemonCommand::RaiseWindow { window_name, sender } => {
+                    let result = self
+                        .open_windows
+                        .get(&window_name)
+                        .with_context(|| format!("Tried to raise window named '{}', but no such window was open", window_name))
+                        .map(|eww_window| display_backend::raise_window(&eww_window.gtk_window));
+                    sender.respond_with_result(result)?;
+                }
+                DaemonCommand::MoveWindowToMonitor { window_name, monitor } => {
+                    self.move_window_to_monitor(&window_name, monitor)?;
+                }
                 DaemonCommand::PrintState { all, sender } => {
                     let scope_graph = self.scope_graph.borrow();
                     let used_globals_names = scope_graph.currently_used_globals();
@@ -198,14 +337,18 @@ impl App {
                         .join("\n");
                     sender.send_success(output)?
                 }
-                DaemonCommand::GetVar { name, sender } => {
-                    let scope_graph = &*self.scope_graph.borrow();
-                    let vars = &scope_graph.scope_at(scope_graph.root_index).expect("No root scope in graph").data;
-                    match vars.get(name.as_str()) {
-                        Some(x) => sender.send_success(x.to_string())?,
-                        None => sender.send_failure(format!("Variable not found \"{}\"", name))?,
+                DaemonCommand::GetVar { name, sender } => match self.unused_magic_var(&VarName(name.clone())) {
+                    // Sampling may take a while, so the script var handler responds once it is done.
+                    Some(magic_var) => self.script_var_handler.sample(magic_var, sender),
+                    None => {
+                        let scope_graph = &*self.scope_graph.borrow();
+                        let vars = &scope_graph.scope_at(scope_graph.root_index).expect("No root scope in graph").data;
+                        match vars.get(name.as_str()) {
+                            Some(x) => sender.send_success(x.to_string())?,
+                            None => sender.send_failure(format!("Variable not found \"{}\"", name))?,
+                        }
                     }
-                }
+                },
                 DaemonCommand::PrintWindows(sender) => {
                     let output = self
                         .eww_config
@@ -222,7 +365,42 @@ impl App {
                     let output = format!("{:#?}", &self);
                     sender.send_success(output)?
                 }
+                DaemonCommand::PauseVars { names, sender } => {
+                    let errors = names.into_iter().map(|name| self.pause_script_var(name)).filter_map(Result::err);
+                    sender.respond_with_error_list(errors)?;
+                }
+                DaemonCommand::ResumeVars { names, sender } => {
+                    let errors = names.into_iter().map(|name| self.resume_script_var(name)).filter_map(Result::err);
+                    sender.respond_with_error_list(errors)?;
+                }
+                DaemonCommand::SetLogLevel { level, sender } => match level.map(|level| level.parse()).transpose() {
+                    // the filter configured via RUST_LOG still applies below the max level, so changing it would have no effect.
+                    Ok(Some(_)) if std::env::var("RUST_LOG").is_ok() => sender.send_failure(
+                        "The log level can not be changed while the daemon is configured via the RUST_LOG environment variable"
+                            .to_string(),
+                    )?,
+                    Ok(level) => {
+                        if let Some(level) = level {
+                            log::set_max_level(level);
+                            log::info!("Changed log level to {}", level);
+                        }
+                        sender.send_success(log::max_level().to_string().to_lowercase())?
+                    }
+                    Err(err) => sender.send_failure(format!("Invalid log level: {}", err))?,
+                },
                 DaemonCommand::PrintGraph(sender) => sender.send_success(self.scope_graph.borrow().visualize())?,
+                DaemonCommand::PrintWidgetArgs { window_name, widget, sender } => {
+                    match self.get_widget_args(&window_name, &widget) {
+                        Ok(args) => sender.send_success(args)?,
+                        Err(err) => sender.send_failure(err.to_string())?,
+                    }
+                }
+                DaemonCommand::GetWidgetGeometry { window_name, widget_id, sender } => {
+                    match self.get_widget_geometry(&window_name, &widget_id) {
+                        Ok(geometry) => sender.send_success(geometry)?,
+                        Err(err) => sender.send_failure(err.to_string())?,
+                    }
+                }
             }
         };
 
@@ -232,7 +410,7 @@ impl App {
     }
 
     fn stop_application(&mut self) {
-        self.script_var_handler.stop_all();
+        self.script_var_handler.stop_all_and_wait();
         for (_, window) in self.open_windows.drain() {
             window.close();
         }
@@ -240,11 +418,31 @@ impl App {
     }
 
     fn update_global_state(&mut self, fieldname: VarName, value: DynVal) {
+        // A value set outside of `eww update --for` replaces a pending temporary override, which must not be reverted anymore.
+        self.temporary_var_overrides.remove(&fieldname);
+        self.set_global_value(fieldname, value);
+    }
+
+    /// Set the value of a global variable, updating the variables derived from it.
+    fn set_global_value(&mut self, fieldname: VarName, value: DynVal) {
         let result = self.scope_graph.borrow_mut().update_global_value(&fieldname, value);
         if let Err(err) = result {
             error_handling_ctx::print_error(err);
         }
 
+        let derived_vars = self.eww_config.get_derived_var_links(&fieldname).to_vec();
+        for derived_var in derived_vars {
+            if let Some(expr) = self.eww_config.get_derived_var(&derived_var) {
+                let scope_graph = self.scope_graph.borrow();
+                let value = scope_graph.evaluate_simplexpr_in_scope(scope_graph.root_index, expr);
+                drop(scope_graph);
+                match value {
+                    Ok(value) => self.update_global_state(derived_var, value),
+                    Err(err) => error_handling_ctx::print_error(err),
+                }
+            }
+        }
+
         if let Ok(linked_poll_vars) = self.eww_config.get_poll_var_link(&fieldname) {
             linked_poll_vars.iter().filter_map(|name| self.eww_config.get_script_var(name).ok()).for_each(|var| {
                 if let ScriptVarDefinition::Poll(poll_var) = var {
@@ -253,7 +451,7 @@ impl App {
                         .evaluate_simplexpr_in_scope(scope_graph.root_index, &poll_var.run_while_expr)
                         .map(|v| v.as_bool());
                     match run_while {
-                        Ok(Ok(true)) => self.script_var_handler.add(var.clone()),
+                        Ok(Ok(true)) => self.start_script_var(var.clone()),
                         Ok(Ok(false)) => self.script_var_handler.stop_for_variable(poll_var.name.clone()),
                         Ok(Err(err)) => error_handling_ctx::print_error(anyhow!(err)),
                         Err(err) => error_handling_ctx::print_error(anyhow!(err)),
@@ -263,23 +461,195 @@ impl App {
         }
     }
 
+    /// Write the currently tracked script var errors into `EWW_ERRORS`.
+    /// This only happens while `EWW_ERRORS` is in use, such that failing scripts don't cause scope graph updates
+    /// for configurations that don't look at their errors.
+    fn publish_script_var_errors(&mut self) -> Result<()> {
+        let errors = DynVal::from_string(serde_json::to_string(&self.script_var_errors)?);
+        self.update_global_state(VarName::from("EWW_ERRORS"), errors);
+        Ok(())
+    }
+
+    /// Start running the script of a script var, unless it has been paused.
+    /// The working directory of the script is evaluated using the current global values.
+    fn start_script_var(&self, script_var: ScriptVarDefinition) {
+        if self.paused_vars.contains(script_var.name()) {
+            log::debug!("Not starting paused variable {}", script_var.name());
+            return;
+        }
+        let scope_graph = self.scope_graph.borrow();
+        let globals = &scope_graph.scope_at(scope_graph.root_index).expect("No root scope in graph").data;
+        match config::script_var::eval_cwd(&script_var, globals) {
+            Ok(cwd) => self.script_var_handler.add(script_var, cwd),
+            Err(err) => error_handling_ctx::print_error(err),
+        }
+    }
+
+    /// Stop the script of a script var, keeping its current value until it is resumed.
+    fn pause_script_var(&mut self, name: VarName) -> Result<()> {
+        self.eww_config.get_script_var(&name)?;
+        log::info!("Pausing variable {}", name);
+        self.script_var_handler.stop_for_variable(name.clone());
+        self.paused_vars.insert(name);
+        Ok(())
+    }
+
+    /// Resume the script of a paused script var, starting it again if it is currently in use
+    /// and, for poll vars, its `:run-while` condition holds.
+    fn resume_script_var(&mut self, name: VarName) -> Result<()> {
+        let script_var = self.eww_config.get_script_var(&name)?.clone();
+        if !self.paused_vars.remove(&name) {
+            bail!("Variable {} is not paused", name);
+        }
+        log::info!("Resuming variable {}", name);
+        if !self.scope_graph.borrow().currently_used_globals().contains(&name) {
+            return Ok(());
+        }
+        if let ScriptVarDefinition::Poll(poll_var) = &script_var {
+            let scope_graph = self.scope_graph.borrow();
+            let run_while =
+                scope_graph.evaluate_simplexpr_in_scope(scope_graph.root_index, &poll_var.run_while_expr)?.as_bool()?;
+            if !run_while {
+                return Ok(());
+            }
+        }
+        self.start_script_var(script_var);
+        Ok(())
+    }
+
+    /// Update a global variable and schedule it to be reverted to its previous value after the given duration.
+    /// If the variable is already temporarily overridden, the pending revert is re-armed,
+    /// keeping the value from before the first override.
+    fn update_global_state_temporarily(&mut self, name: VarName, value: DynVal, duration: std::time::Duration) -> Result<()> {
+        let current_value = {
+            let scope_graph = self.scope_graph.borrow();
+            let globals = &scope_graph.scope_at(scope_graph.root_index).expect("No root scope in graph").data;
+            globals.get(&name).with_context(|| format!("Variable not found \"{}\"", name))?.clone()
+        };
+        let generation = self.temporary_var_overrides.insert(name.clone(), current_value);
+        self.set_global_value(name.clone(), value);
+
+        let app_evt_send = self.app_evt_send.clone();
+        glib::timeout_add_local_once(duration, move || {
+            let _ = app_evt_send.send(DaemonCommand::RevertTemporaryVar { name, generation });
+        });
+        Ok(())
+    }
+
     fn close_window(&mut self, window_name: &str) -> Result<()> {
         let eww_window = self
             .open_windows
             .remove(window_name)
             .with_context(|| format!("Tried to close window named '{}', but no such window was open", window_name))?;
 
-        self.scope_graph.borrow_mut().remove_scope(eww_window.scope_index);
+        let scope_index = eww_window.scope_index;
+        match eww_window.definition.close_animation {
+            // The scope of the window is only removed once the window has been destroyed, as it is still shown until then.
+            Some(animation) => {
+                let app_evt_send = self.app_evt_send.clone();
+                eww_window.close_animated(animation, move || {
+                    let _ = app_evt_send.send(DaemonCommand::RemoveClosedWindowScope { scope_index });
+                });
+            }
+            None => {
+                eww_window.close();
+                self.remove_closed_window_scope(scope_index);
+            }
+        }
+        Ok(())
+    }
 
-        eww_window.close();
+    /// Remove the scope of a window that has been closed, and stop the script-vars that are no longer used.
+    fn remove_closed_window_scope(&mut self, scope_index: ScopeIndex) {
+        self.scope_graph.borrow_mut().remove_scope(scope_index);
 
         let unused_variables = self.scope_graph.borrow().currently_unused_globals();
         for unused_var in unused_variables {
             log::debug!("stopping for {}", &unused_var);
             self.script_var_handler.stop_for_variable(unused_var.clone());
         }
+    }
+
+    /// Magic variables are only updated while they are in use.
+    /// Get the definition of the given variable if it is a magic variable that is currently not in use,
+    /// such that its value can be sampled once, without starting to update it.
+    fn unused_magic_var(&self, name: &VarName) -> Option<ScriptVarDefinition> {
+        let is_magic = config::inbuilt::INBUILT_VAR_NAMES.contains(&name.0.as_str())
+            || config::inbuilt::INBUILT_LISTEN_VAR_NAMES.contains(&name.0.as_str());
+        if !is_magic || self.scope_graph.borrow().currently_used_globals().contains(name) {
+            return None;
+        }
+        self.eww_config.get_script_var(name).ok().cloned()
+    }
 
-        Ok(())
+    /// Get the values the arguments of a custom widget within an open window resolved to, as a json object.
+    /// The widget is given either by its name, or by the index of the scope of the widget invocation.
+    fn get_widget_args(&self, window_name: &str, widget: &str) -> Result<String> {
+        let window = self
+            .open_windows
+            .get(window_name)
+            .with_context(|| format!("Tried to inspect window named '{}', but no such window was open", window_name))?;
+        let widget_defs = self.eww_config.get_widget_definitions();
+        let scope_graph = self.scope_graph.borrow();
+        let invocations: Vec<ScopeIndex> = scope_graph
+            .descendants_of(window.scope_index)
+            .into_iter()
+            .filter(|index| {
+                let scope = scope_graph.scope_at(*index);
+                let is_custom_widget = scope.map_or(false, |scope| widget_defs.contains_key(&scope.name));
+                let matches = widget.parse::<usize>() == Ok(index.0) || scope.map_or(false, |scope| scope.name == widget);
+                is_custom_widget && matches
+            })
+            .collect();
+        let scope_index = match invocations.as_slice() {
+            [] => bail!("No custom widget '{}' found in window '{}'", widget, window_name),
+            [index] => *index,
+            _ => bail!(
+                "The widget '{}' is used multiple times in window '{}'. Select one by its scope index: {}",
+                widget,
+                window_name,
+                invocations.iter().map(|index| index.0).join(", ")
+            ),
+        };
+        let scope = scope_graph.scope_at(scope_index).context("Scope not in graph")?;
+        let widget_def = widget_defs.get(&scope.name).context("Widget definition not found")?;
+        let args: serde_json::Map<String, serde_json::Value> = widget_def
+            .expected_args
+            .iter()
+            .map(|spec| {
+                let value = scope.data.get(&VarName(spec.name.0.clone()));
+                (spec.name.0.clone(), value.map_or(serde_json::Value::Null, |value| serde_json::Value::String(value.to_string())))
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&args)?)
+    }
+
+    /// Get the allocation of the widget with the given `:id` in screen coordinates, as a json object.
+    fn get_widget_geometry(&self, window_name: &str, widget_id: &str) -> Result<String> {
+        // Layer shell surfaces don't know their position on screen, and always report their origin as (0, 0).
+        if cfg!(feature = "wayland") {
+            bail!("Getting the geometry of a widget is not supported on wayland, as the position of windows is unknown there");
+        }
+        let window = self
+            .open_windows
+            .get(window_name)
+            .with_context(|| format!("Tried to inspect window named '{}', but no such window was open", window_name))?;
+        let widget = find_widget_by_id(window.gtk_window.upcast_ref(), widget_id)
+            .with_context(|| format!("No widget with the id '{}' found in window '{}'", widget_id, window_name))?;
+        if !widget.is_mapped() {
+            bail!("The widget with the id '{}' is not currently mapped", widget_id);
+        }
+        let (window_x, window_y) =
+            widget.translate_coordinates(&window.gtk_window, 0, 0).context("Failed to get the position of the widget")?;
+        let gdk_window = window.gtk_window.window().context("Window has not been realized")?;
+        let (_, origin_x, origin_y) = gdk_window.origin();
+        let geometry = serde_json::json!({
+            "x": origin_x + window_x,
+            "y": origin_y + window_y,
+            "width": widget.allocated_width(),
+            "height": widget.allocated_height(),
+        });
+        Ok(geometry.to_string())
     }
 
     fn open_window(
@@ -299,6 +669,14 @@ impl App {
         let open_result: Result<_> = try {
             let mut window_def = self.eww_config.get_window(window_name)?.clone();
             window_def.geometry = window_def.geometry.map(|x| x.override_if_given(anchor, pos, size));
+            window_def.monitor_number = match (monitor, &window_def.monitor_expr) {
+                (Some(monitor), _) => Some(monitor),
+                (None, Some(monitor_expr)) => {
+                    let scope_graph = self.scope_graph.borrow();
+                    Some(scope_graph.evaluate_simplexpr_in_scope(scope_graph.root_index, monitor_expr)?.as_i32()?)
+                }
+                (None, None) => window_def.monitor_number,
+            };
 
             let root_index = self.scope_graph.borrow().root_index;
 
@@ -317,18 +695,28 @@ impl App {
                 None,
             )?;
 
-            let monitor_geometry = get_monitor_geometry(monitor.or(window_def.monitor_number))?;
+            // a monitor given explicitly when opening the window takes precedence over the monitor expression
+            if let (None, Some(monitor_expr)) = (monitor, window_def.monitor_expr.clone()) {
+                self.watch_monitor_expr(window_name, window_scope, monitor_expr)?;
+            }
+
+            let monitor_geometry = get_monitor_geometry(window_def.monitor_number)?;
 
             let eww_window = initialize_window(monitor_geometry, root_widget, window_def, window_scope)?;
             eww_window.gtk_window.style_context().add_class(&window_name.to_string());
 
             // initialize script var handlers for variables that where not used before opening this window.
             // TODO maybe this could be handled by having a track_newly_used_variables function in the scope tree?
-            for used_var in self.scope_graph.borrow().variables_used_in_self_or_subscopes_of(eww_window.scope_index) {
-                if let Ok(script_var) = self.eww_config.get_script_var(&used_var) {
-                    self.script_var_handler.add(script_var.clone());
+            let used_vars = self.scope_graph.borrow().variables_used_in_self_or_subscopes_of(eww_window.scope_index);
+            for used_var in &used_vars {
+                if let Ok(script_var) = self.eww_config.get_script_var(used_var) {
+                    self.start_script_var(script_var.clone());
                 }
             }
+            // errors that occurred while `EWW_ERRORS` was not in use have not been published yet.
+            if used_vars.contains(&VarName::from("EWW_ERRORS")) {
+                self.publish_script_var_errors()?;
+            }
 
             eww_window.gtk_window.connect_destroy({
                 let scope_graph_sender = self.scope_graph.borrow().event_sender.clone();
@@ -347,16 +735,85 @@ impl App {
         }
     }
 
+    /// Move the window to a different monitor whenever the value of its monitor expression changes.
+    /// Changes are debounced, such that the window is only moved once the value has settled.
+    fn watch_monitor_expr(&self, window_name: &str, window_scope: ScopeIndex, monitor_expr: SimplExpr) -> Result<()> {
+        let app_evt_send = self.app_evt_send.clone();
+        let window_name = window_name.to_string();
+        let generation = Rc::new(Cell::new(0u64));
+        self.scope_graph.borrow_mut().register_listener(
+            window_scope,
+            Listener {
+                needed_variables: monitor_expr.collect_var_refs(),
+                f: Box::new(move |_, values| {
+                    let monitor = monitor_expr.eval(&values)?.as_i32()?;
+                    generation.set(generation.get() + 1);
+                    let expected_generation = generation.get();
+                    let generation = generation.clone();
+                    let app_evt_send = app_evt_send.clone();
+                    let window_name = window_name.clone();
+                    glib::timeout_add_local_once(MONITOR_CHANGE_DEBOUNCE, move || {
+                        if generation.get() == expected_generation {
+                            let _ = app_evt_send.send(DaemonCommand::MoveWindowToMonitor { window_name, monitor });
+                        }
+                    });
+                    Ok(())
+                }),
+            },
+        )
+    }
+
+    /// Move an open window to the given monitor, keeping its widgets and the geometry it was opened with.
+    fn move_window_to_monitor(&mut self, window_name: &str, monitor: i32) -> Result<()> {
+        let eww_window = match self.open_windows.get_mut(window_name) {
+            Some(eww_window) if eww_window.definition.monitor_number != Some(monitor) => eww_window,
+            _ => return Ok(()),
+        };
+        log::info!("Moving window {} to monitor {}", window_name, monitor);
+        let monitor_geometry = get_monitor_geometry(Some(monitor))?;
+        eww_window.definition.monitor_number = Some(monitor);
+        eww_window.monitor_geometry.set(monitor_geometry);
+        let window = &eww_window.gtk_window;
+        let window_def = &eww_window.definition;
+        apply_window_size(window_def, monitor_geometry, window);
+
+        #[cfg(feature = "x11")]
+        {
+            if let Some(geometry) = window_def.geometry {
+                apply_window_position(geometry, monitor_geometry, window)?;
+            }
+            display_backend::set_xprops(window, monitor_geometry, window_def)?;
+        }
+
+        #[cfg(feature = "wayland")]
+        {
+            use glib::ObjectExt;
+            // Moving the surface remaps it, which must not terminate the commands run by the widgets of the window.
+            window.block_signal(&eww_window.unmap_handler);
+            let moved = display_backend::move_window_to_monitor(window, window_def, monitor_geometry);
+            window.unblock_signal(&eww_window.unmap_handler);
+            moved.with_context(|| format!("monitor {} is unavailable", monitor))?;
+        }
+        Ok(())
+    }
+
     /// Load the given configuration, reloading all script-vars and attempting to reopen all windows that where opened.
     pub fn load_config(&mut self, config: config::EwwConfig) -> Result<()> {
         log::info!("Reloading windows");
 
         self.script_var_handler.stop_all();
-        self.script_var_handler = script_var_handler::init(self.app_evt_send.clone());
+        self.script_var_handler =
+            script_var_handler::init(self.app_evt_send.clone(), self.paths.get_poll_cache_file().to_path_buf());
 
         log::trace!("loading config: {:#?}", config);
 
         self.eww_config = config;
+        self.script_var_errors.clear();
+        // all variables are reset to their initial values, so paused variables have to run again to get a value.
+        if !self.paused_vars.is_empty() {
+            log::info!("Resuming paused variables: {}", self.paused_vars.iter().join(", "));
+            self.paused_vars.clear();
+        }
         self.scope_graph.borrow_mut().clear(self.eww_config.generate_initial_state()?);
 
         let window_names: Vec<String> =
@@ -386,11 +843,7 @@ fn initialize_window(
     window.set_position(gtk::WindowPosition::None);
     window.set_gravity(gdk::Gravity::Center);
 
-    if let Some(geometry) = window_def.geometry {
-        let actual_window_rect = get_window_rectangle(geometry, monitor_geometry);
-        window.set_size_request(actual_window_rect.width, actual_window_rect.height);
-        window.set_default_size(actual_window_rect.width, actual_window_rect.height);
-    }
+    apply_window_size(&window_def, monitor_geometry, &window);
     window.set_decorated(false);
     window.set_skip_taskbar_hint(true);
     window.set_skip_pager_hint(true);
@@ -403,23 +856,106 @@ fn initialize_window(
 
     window.realize();
 
+    let monitor_geometry = Rc::new(Cell::new(monitor_geometry));
+
     #[cfg(feature = "x11")]
     {
         if let Some(geometry) = window_def.geometry {
-            let _ = apply_window_position(geometry, monitor_geometry, &window);
+            let _ = apply_window_position(geometry, monitor_geometry.get(), &window);
             if window_def.backend_options.window_type != yuck::config::backend_window_options::WindowType::Normal {
+                let monitor_geometry = monitor_geometry.clone();
                 window.connect_configure_event(move |window, _| {
-                    let _ = apply_window_position(geometry, monitor_geometry, window);
+                    let _ = apply_window_position(geometry, monitor_geometry.get(), window);
                     false
                 });
             }
         }
-        display_backend::set_xprops(&window, monitor_geometry, &window_def)?;
+        display_backend::set_xprops(&window, monitor_geometry.get(), &window_def)?;
     }
 
+    // Commands that the widgets of the window are still running are terminated once it is unmapped, i.e. when it closes.
+    let unmap_handler = window.connect_unmap(|gtk_window| crate::widgets::kill_window_commands(gtk_window));
+
     window.show_all();
 
-    Ok(EwwWindow { name: window_def.name.clone(), definition: window_def, gtk_window: window, scope_index: window_scope })
+    Ok(EwwWindow {
+        name: window_def.name.clone(),
+        definition: window_def,
+        gtk_window: window,
+        scope_index: window_scope,
+        monitor_geometry,
+        unmap_handler: Rc::new(unmap_handler),
+    })
+}
+
+/// Request the size given by the geometry of the window relative to the monitor, respecting its size constraints.
+fn apply_window_size(window_def: &WindowDefinition, monitor_geometry: gdk::Rectangle, window: &gtk::Window) {
+    let size_constraints = WindowSizeConstraints::resolve(window_def, monitor_geometry);
+    if let Some(geometry) = window_def.geometry {
+        let actual_window_rect = get_window_rectangle(geometry, monitor_geometry);
+        // Geometry hints are not respected by every backend, so the requested size is clamped as well.
+        let (width, height) = size_constraints.clamp(actual_window_rect.width, actual_window_rect.height);
+        window.set_size_request(width, height);
+        window.set_default_size(width, height);
+    }
+    size_constraints.apply_geometry_hints(window);
+}
+
+/// The `min-*` and `max-*` sizes of a window, resolved relative to the monitor the window is opened on.
+struct WindowSizeConstraints {
+    min_width: Option<i32>,
+    min_height: Option<i32>,
+    max_width: Option<i32>,
+    max_height: Option<i32>,
+}
+
+impl WindowSizeConstraints {
+    fn resolve(window_def: &WindowDefinition, monitor_geometry: gdk::Rectangle) -> Self {
+        let width = |x: Option<NumWithUnit>| x.map(|x| x.pixels_relative_to(monitor_geometry.width));
+        let height = |x: Option<NumWithUnit>| x.map(|x| x.pixels_relative_to(monitor_geometry.height));
+        Self {
+            min_width: width(window_def.min_width),
+            min_height: height(window_def.min_height),
+            max_width: width(window_def.max_width),
+            max_height: height(window_def.max_height),
+        }
+    }
+
+    fn clamp(&self, width: i32, height: i32) -> (i32, i32) {
+        let clamp = |size: i32, min: Option<i32>, max: Option<i32>| {
+            let size = min.map_or(size, |min| size.max(min));
+            max.map_or(size, |max| size.min(max))
+        };
+        (clamp(width, self.min_width, self.max_width), clamp(height, self.min_height, self.max_height))
+    }
+
+    fn apply_geometry_hints(&self, window: &gtk::Window) {
+        let mut hints = gdk::WindowHints::empty();
+        if self.min_width.is_some() || self.min_height.is_some() {
+            hints |= gdk::WindowHints::MIN_SIZE;
+        }
+        if self.max_width.is_some() || self.max_height.is_some() {
+            hints |= gdk::WindowHints::MAX_SIZE;
+        }
+        if hints.is_empty() {
+            return;
+        }
+        // a negative minimum size makes gtk fall back to the size requested by the widgets
+        let geometry = gdk::Geometry {
+            min_width: self.min_width.unwrap_or(-1),
+            min_height: self.min_height.unwrap_or(-1),
+            max_width: self.max_width.unwrap_or(i16::MAX as i32),
+            max_height: self.max_height.unwrap_or(i16::MAX as i32),
+            base_width: 0,
+            base_height: 0,
+            width_inc: 0,
+            height_inc: 0,
+            min_aspect: 0.0,
+            max_aspect: 0.0,
+            win_gravity: gdk::Gravity::Center,
+        };
+        window.set_geometry_hints(None::<&gtk::Widget>, Some(&geometry), hints);
+    }
 }
 
 /// Apply the provided window-positioning rules to the window.
@@ -462,6 +998,15 @@ fn get_monitor_geometry(n: Option<i32>) -> Result<gdk::Rectangle> {
     Ok(monitor.geometry())
 }
 
+/// Find the widget with the given `:id`, which is stored as its widget name, within the given widget.
+fn find_widget_by_id(widget: &gtk::Widget, widget_id: &str) -> Option<gtk::Widget> {
+    if widget.widget_name() == widget_id {
+        return Some(widget.clone());
+    }
+    let container = widget.dynamic_cast_ref::<gtk::Container>()?;
+    container.children().iter().find_map(|child| find_widget_by_id(child, widget_id))
+}
+
 pub fn get_window_rectangle(geometry: WindowGeometry, screen_rect: gdk::Rectangle) -> gdk::Rectangle {
     let (offset_x, offset_y) = geometry.offset.relative_to(screen_rect.width, screen_rect.height);
     let (width, height) = geometry.size.relative_to(screen_rect.width, screen_rect.height);
@@ -7,7 +7,8 @@ use crate::{
 };
 use anyhow::{Context, Result};
 use std::{
-    io::{Read, Write},
+    io::{BufRead, Read, Write},
+    net::Shutdown,
     os::unix::net::UnixStream,
 };
 
@@ -27,14 +28,7 @@ pub fn handle_client_only_action(paths: &EwwPaths, action: ActionClientOnly) ->
 /// Connect to the daemon and send the given request.
 /// Returns the response from the daemon, or None if the daemon did not provide any useful response. An Ok(None) response does _not_ indicate failure.
 pub fn do_server_call(stream: &mut UnixStream, action: &opts::ActionWithServer) -> Result<Option<DaemonResponse>> {
-    log::debug!("Forwarding options to server");
-    stream.set_nonblocking(false).context("Failed to set stream to non-blocking")?;
-
-    let message_bytes = bincode::serialize(&action)?;
-
-    stream.write(&(message_bytes.len() as u32).to_be_bytes()).context("Failed to send command size header to IPC stream")?;
-
-    stream.write_all(&message_bytes).context("Failed to write command to IPC stream")?;
+    send_action(stream, action)?;
 
     let mut buf = Vec::new();
     stream.set_read_timeout(Some(std::time::Duration::from_millis(100))).context("Failed to set read timeout")?;
@@ -47,3 +41,28 @@ pub fn do_server_call(stream: &mut UnixStream, action: &opts::ActionWithServer)
         Some(buf)
     })
 }
+
+/// Start an update stream with the daemon, forwarding every line of the given input to it until the input ends.
+pub fn do_update_stream(stream: &mut UnixStream, input: impl BufRead) -> Result<()> {
+    send_action(stream, &opts::ActionWithServer::UpdateStream)?;
+    for line in input.lines() {
+        let line = line.context("Failed to read update from input")?;
+        stream.write_all(format!("{}\n", line).as_bytes()).context("Failed to write update to IPC stream")?;
+    }
+    stream.shutdown(Shutdown::Write).context("Failed to close IPC stream")?;
+    Ok(())
+}
+
+/// Send an action to the daemon.
+/// The message consists of the size of the serialized action as 4 bytes (in big-endian), followed by the serialized action.
+fn send_action(stream: &mut UnixStream, action: &opts::ActionWithServer) -> Result<()> {
+    log::debug!("Forwarding options to server");
+    stream.set_nonblocking(false).context("Failed to set stream to non-blocking")?;
+
+    let message_bytes = bincode::serialize(&action)?;
+
+    stream.write(&(message_bytes.len() as u32).to_be_bytes()).context("Failed to send command size header to IPC stream")?;
+
+    stream.write_all(&message_bytes).context("Failed to write command to IPC stream")?;
+    Ok(())
+}
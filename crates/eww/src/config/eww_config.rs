@@ -1,5 +1,6 @@
 use anyhow::{bail, Context, Result};
 use eww_shared_util::VarName;
+use itertools::Itertools;
 use std::collections::HashMap;
 use yuck::{
     config::{
@@ -9,11 +10,11 @@ use yuck::{
     error::AstError,
 };
 
-use simplexpr::dynval::DynVal;
+use simplexpr::{dynval::DynVal, SimplExpr};
 
 use crate::{config::inbuilt, error_handling_ctx, widgets::widget_definitions, EwwPaths};
 
-use super::script_var;
+use super::{poll_cache, script_var};
 
 /// Load an [`EwwConfig`] from the config dir of the given [`crate::EwwPaths`],
 /// resetting and applying the global YuckFiles object in [`crate::error_handling_ctx`].
@@ -32,6 +33,14 @@ pub struct EwwConfig {
 
     // Links variable which affect state (active/inactive) of poll var to those poll variables
     poll_var_links: HashMap<VarName, Vec<VarName>>,
+
+    // Variables whose value is derived from an expression, given via `(defvar name :value {...})`
+    derived_vars: HashMap<VarName, SimplExpr>,
+    // Links variables to the derived variables that need to be recomputed when they change
+    derived_var_links: HashMap<VarName, Vec<VarName>>,
+
+    // Values of poll variables with `:cache` from their last run, used instead of their initial value
+    cached_values: HashMap<VarName, DynVal>,
 }
 
 impl Default for EwwConfig {
@@ -42,6 +51,9 @@ impl Default for EwwConfig {
             initial_variables: HashMap::new(),
             script_vars: HashMap::new(),
             poll_var_links: HashMap::new(),
+            derived_vars: HashMap::new(),
+            derived_var_links: HashMap::new(),
+            cached_values: HashMap::new(),
         }
     }
 }
@@ -58,11 +70,12 @@ impl EwwConfig {
         // run some validations on the configuration
         let magic_globals: Vec<_> = inbuilt::INBUILT_VAR_NAMES
             .into_iter()
+            .chain(inbuilt::INBUILT_LISTEN_VAR_NAMES)
             .chain(inbuilt::MAGIC_CONSTANT_NAMES)
             .into_iter()
             .map(|x| VarName::from(x.clone()))
             .collect();
-        yuck::config::validate::validate(&config, magic_globals)?;
+        yuck::config::validate::validate(&config, magic_globals, widget_definitions::BUILTIN_CHILD_VARIABLES)?;
 
         for (name, def) in &config.widget_definitions {
             if widget_definitions::BUILTIN_WIDGET_NAMES.contains(&name.as_str()) {
@@ -74,6 +87,7 @@ impl EwwConfig {
 
         let Config { widget_definitions, window_definitions, mut var_definitions, mut script_vars } = config;
         script_vars.extend(inbuilt::get_inbuilt_vars());
+        script_vars.extend(inbuilt::get_inbuilt_listen_vars());
         var_definitions.extend(inbuilt::get_magic_constants(eww_paths));
 
         let mut poll_var_links = HashMap::<VarName, Vec<VarName>>::new();
@@ -86,12 +100,31 @@ impl EwwConfig {
                     .for_each(|name| poll_var_links.entry(name.clone()).or_default().push(var.name.clone()))
             });
 
+        let is_cached = |name: &VarName| matches!(script_vars.get(name), Some(ScriptVarDefinition::Poll(var)) if var.cache);
+        let cached_values: HashMap<VarName, DynVal> = if script_vars.keys().any(is_cached) {
+            poll_cache::read(eww_paths.get_poll_cache_file()).into_iter().filter(|(name, _)| is_cached(name)).collect()
+        } else {
+            HashMap::new()
+        };
+
+        let derived_vars: HashMap<VarName, SimplExpr> =
+            var_definitions.values().filter_map(|var| Some((var.name.clone(), var.value_expr.clone()?))).collect();
+        let mut derived_var_links = HashMap::<VarName, Vec<VarName>>::new();
+        for (name, expr) in &derived_vars {
+            for var_ref in expr.collect_var_refs() {
+                derived_var_links.entry(var_ref).or_default().push(name.clone());
+            }
+        }
+
         Ok(EwwConfig {
             windows: window_definitions,
             widgets: widget_definitions,
             initial_variables: var_definitions.into_iter().map(|(k, v)| (k, v.initial_value)).collect(),
             script_vars,
             poll_var_links,
+            derived_vars,
+            derived_var_links,
+            cached_values,
         })
     }
 
@@ -100,9 +133,33 @@ impl EwwConfig {
         let mut vars = self
             .script_vars
             .iter()
-            .map(|(name, var)| Ok((name.clone(), script_var::initial_value(var)?)))
+            .map(|(name, var)| match self.cached_values.get(name) {
+                Some(cached_value) => Ok((name.clone(), cached_value.clone())),
+                None => Ok((name.clone(), script_var::initial_value(var, &self.initial_variables)?)),
+            })
             .collect::<Result<HashMap<_, _>>>()?;
         vars.extend(self.initial_variables.clone());
+
+        // Derived variables may depend on each other, so evaluate them in dependency order.
+        let mut pending: Vec<_> = self.derived_vars.iter().collect();
+        for (name, _) in &pending {
+            vars.remove(*name);
+        }
+        while !pending.is_empty() {
+            let (ready, waiting): (Vec<_>, Vec<_>) =
+                pending.into_iter().partition(|(_, expr)| expr.collect_var_refs().iter().all(|var| vars.contains_key(var)));
+            if ready.is_empty() {
+                bail!(
+                    "Failed to evaluate variables with a cyclic dependency: {}",
+                    waiting.iter().map(|(name, _)| name.to_string()).join(", ")
+                );
+            }
+            for (name, expr) in ready {
+                let value = expr.eval(&vars).with_context(|| format!("Failed to evaluate the value of {}", name))?;
+                vars.insert(name.clone(), value);
+            }
+            pending = waiting;
+        }
         Ok(vars)
     }
 
@@ -128,6 +185,15 @@ impl EwwConfig {
         &self.widgets
     }
 
+    pub fn get_derived_var(&self, name: &VarName) -> Option<&SimplExpr> {
+        self.derived_vars.get(name)
+    }
+
+    /// Get the derived variables that need to be recomputed when the given variable changes.
+    pub fn get_derived_var_links(&self, name: &VarName) -> &[VarName] {
+        self.derived_var_links.get(name).map(|x| x.as_slice()).unwrap_or_default()
+    }
+
     pub fn get_poll_var_link(&self, name: &VarName) -> Result<&Vec<VarName>> {
         self.poll_var_links.get(name).with_context(|| format!("{} does not links to any poll variable", name.0))
     }
@@ -2,15 +2,19 @@ use std::{collections::HashMap, time::Duration};
 
 use simplexpr::{dynval::DynVal, SimplExpr};
 use yuck::config::{
-    script_var_definition::{PollScriptVar, ScriptVarDefinition, VarSource},
+    script_var_definition::{
+        BuiltinListenSource, ListenScriptVar, ListenSource, PollScriptVar, ScriptVarDefinition, VarFormat, VarSource,
+    },
     var_definition::VarDefinition,
 };
 
 use crate::{config::system_stats::*, EwwPaths};
 use eww_shared_util::VarName;
 
+/// Define the builtin magic variables, polled with the given interval.
+/// The interval of a single variable can be overridden by giving it in brackets after its name.
 macro_rules! define_builtin_vars {
-    ($interval:expr, $($name:literal => $fun:expr),*$(,)?) => {
+    ($interval:expr, $($name:literal $([$var_interval:expr])? => $fun:expr),*$(,)?) => {
         pub static INBUILT_VAR_NAMES: &[&'static str] = &[$($name),*];
         pub fn get_inbuilt_vars() -> HashMap<VarName, ScriptVarDefinition> {
             maplit::hashmap! {
@@ -21,7 +25,10 @@ macro_rules! define_builtin_vars {
                     run_while_var_refs: Vec::new(),
                     command: VarSource::Function($fun),
                     initial_value: None,
-                    interval: $interval,
+                    interval: { let interval = $interval; $(let interval = $var_interval;)? interval },
+                    cwd: None,
+                    format: VarFormat::Raw,
+                    cache: false,
                     name_span: eww_shared_util::span::Span::DUMMY,
                 })
                 ),*
@@ -62,6 +69,35 @@ define_builtin_vars! { Duration::new(2, 0),
     // @desc EWW_NET - Bytes up/down on all interfaces
     // @prop { <name>: { up, down } }
     "EWW_NET" => || Ok(DynVal::from(net())),
+
+    // @desc EWW_TIME - The current time as seconds since the unix epoch, updated every second
+    "EWW_TIME" [Duration::from_secs(1)] => || Ok(DynVal::from_string(
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs().to_string()
+    )),
+}
+
+/// Magic variables whose values are rates since their previous update, i.e. the CPU usage or the network traffic.
+/// They need to be sampled twice, one interval apart, to get a meaningful value.
+pub static RATE_BASED_VAR_NAMES: &[&str] = &["EWW_CPU", "EWW_NET"];
+
+/// Magic variables that are updated by a task of the script var handler as soon as their source changes.
+pub static INBUILT_LISTEN_VAR_NAMES: &[&str] = &["EWW_MPRIS"];
+
+pub fn get_inbuilt_listen_vars() -> HashMap<VarName, ScriptVarDefinition> {
+    maplit::hashmap! {
+        // @desc EWW_MPRIS - Information on the active MPRIS media player (the one that is currently playing, or the first one found). `null` if there is no player. Position and length are given in seconds. Updated as soon as a player changes, the position of playing players is updated every second.
+        // @prop { player, status, title, artist, album, art_url, position, length, players: [{ player, status, title, ... }] }
+        VarName::from("EWW_MPRIS") => ScriptVarDefinition::Listen(ListenScriptVar {
+            name: VarName::from("EWW_MPRIS"),
+            command: ListenSource::Builtin(BuiltinListenSource::Mpris),
+            initial_value: DynVal::from_string("null".to_string()),
+            cwd: None,
+            format: VarFormat::Raw,
+            max_lines: None,
+            command_span: eww_shared_util::span::Span::DUMMY,
+            name_span: eww_shared_util::span::Span::DUMMY,
+        }),
+    }
 }
 
 macro_rules! define_magic_constants {
@@ -72,6 +108,7 @@ macro_rules! define_magic_constants {
                 $(VarName::from($name) => VarDefinition {
                     name: VarName::from($name),
                     initial_value: $value,
+                    value_expr: None,
                     span: eww_shared_util::span::Span::DUMMY
                 }),*
             }
@@ -93,4 +130,7 @@ define_magic_constants! { eww_paths,
     "EWW_EXECUTABLE" => DynVal::from_string(
         std::env::current_exe().map(|x| x.to_string_lossy().into_owned()).unwrap_or_else(|_| "eww".to_string()),
     ),
+    // @desc EWW_ERRORS - The latest error of every `defpoll` or `deflisten` variable whose script is currently failing. Errors are cleared once the script succeeds again. The variable is only updated while it is used in an open window, so failing scripts cause no updates in configurations that don't use it.\nExample: `{EWW_ERRORS["cpu_temp"] ?: ""}`
+    // @prop { <variable_name>: error }
+    "EWW_ERRORS" => DynVal::from_string("{}".to_string()),
 }
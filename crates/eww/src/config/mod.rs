@@ -1,5 +1,6 @@
 pub mod eww_config;
 pub mod inbuilt;
+pub mod poll_cache;
 pub mod script_var;
 pub mod system_stats;
 pub mod window_definition;
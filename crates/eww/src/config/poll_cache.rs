@@ -0,0 +1,63 @@
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use anyhow::{Context, Result};
+use eww_shared_util::VarName;
+use once_cell::sync::Lazy;
+use simplexpr::dynval::DynVal;
+
+/// The values currently stored in the cache file, loaded when the first value is stored.
+static CACHED_VALUES: Lazy<Mutex<Option<HashMap<String, String>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Read the values of polling variables with `:cache` that were stored on disk.
+/// A missing or corrupt cache is treated as empty, such that the variables fall back to their normal initial value.
+pub fn read(path: &Path) -> HashMap<VarName, DynVal> {
+    read_file(path).into_iter().map(|(name, value)| (VarName(name), DynVal::from_string(value))).collect()
+}
+
+/// Store the value of a polling variable in the cache, writing it to disk if it changed.
+pub fn store(path: &Path, name: &VarName, value: &DynVal) -> Result<()> {
+    let mut cached_values = CACHED_VALUES.lock().unwrap();
+    let cached_values = cached_values.get_or_insert_with(|| read_file(path));
+    if cached_values.get(&name.0) == Some(&value.0) {
+        return Ok(());
+    }
+    cached_values.insert(name.0.clone(), value.0.clone());
+
+    // Write to a temporary file first, such that the cache does not get corrupted when eww is stopped while writing.
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string(&cached_values)?)
+        .with_context(|| format!("Failed to write poll cache to {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("Failed to write poll cache to {}", path.display()))?;
+    Ok(())
+}
+
+fn read_file(path: &Path) -> HashMap<String, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return HashMap::new(),
+    };
+    serde_json::from_str(&content).unwrap_or_else(|err| {
+        log::warn!("Ignoring corrupt poll cache {}: {}", path.display(), err);
+        HashMap::new()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_invalid_cache() {
+        let dir = std::env::temp_dir().join(format!("eww_poll_cache_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.json");
+        assert!(read(&path).is_empty());
+
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(read(&path).is_empty());
+
+        std::fs::write(&path, r#"{"weather": "sunny"}"#).unwrap();
+        assert_eq!(read(&path).get(&VarName::from("weather")), Some(&DynVal::from("sunny")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,11 @@
-use std::process::Command;
+use std::{collections::HashMap, process::Command};
 
 use anyhow::{anyhow, bail, Context, Result};
 use codespan_reporting::diagnostic::Severity;
 use eww_shared_util::{Span, VarName};
 use simplexpr::dynval::DynVal;
 use yuck::{
-    config::script_var_definition::{ScriptVarDefinition, VarSource},
+    config::script_var_definition::{ScriptVarDefinition, VarFormat, VarSource},
     gen_diagnostic,
 };
 
@@ -20,7 +20,9 @@ pub fn create_script_var_failed_warn(span: Span, var_name: &VarName, error_outpu
     })
 }
 
-pub fn initial_value(var: &ScriptVarDefinition) -> Result<DynVal> {
+/// Get the initial value of a script var, running its script if it has none.
+/// The working directory of the script is evaluated using the given global values.
+pub fn initial_value(var: &ScriptVarDefinition, values: &HashMap<VarName, DynVal>) -> Result<DynVal> {
     match var {
         ScriptVarDefinition::Poll(x) => match &x.initial_value {
             Some(value) => Ok(value.clone()),
@@ -28,20 +30,36 @@ pub fn initial_value(var: &ScriptVarDefinition) -> Result<DynVal> {
                 VarSource::Function(f) => f()
                     .map_err(|err| anyhow!(err))
                     .with_context(|| format!("Failed to compute initial value for {}", &var.name())),
-                VarSource::Shell(span, command) => {
-                    run_command(command).map_err(|e| anyhow!(create_script_var_failed_warn(*span, var.name(), &e.to_string())))
-                }
+                VarSource::Shell(span, command) => eval_cwd(var, values)
+                    .and_then(|cwd| run_command(command, cwd.as_deref()))
+                    .and_then(|output| parse_output(&output.0, x.format))
+                    .map_err(|e| anyhow!(create_script_var_failed_warn(*span, var.name(), &e.to_string()))),
             },
         },
 
         ScriptVarDefinition::Listen(var) => Ok(var.initial_value.clone()),
+        ScriptVarDefinition::Dbus(var) => Ok(var.initial_value.clone()),
     }
 }
 
-/// Run a command and get the output
-pub fn run_command(cmd: &str) -> Result<DynVal> {
+/// Evaluate the working directory of the script of a variable, if it has one.
+pub fn eval_cwd(var: &ScriptVarDefinition, values: &HashMap<VarName, DynVal>) -> Result<Option<String>> {
+    var.cwd_expr()
+        .map(|cwd| cwd.eval(values).map(|cwd| cwd.0))
+        .transpose()
+        .with_context(|| format!("Failed to evaluate the working directory of {}", var.name()))
+}
+
+/// Run a command and get the output.
+/// If a working directory is given, the command is run in that directory instead of the configuration directory.
+pub fn run_command(cmd: &str, cwd: Option<&str>) -> Result<DynVal> {
     log::debug!("Running command: {}", cmd);
-    let command = Command::new("/bin/sh").arg("-c").arg(cmd).output()?;
+    let mut command = Command::new("/bin/sh");
+    command.arg("-c").arg(cmd);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    let command = command.output()?;
     if !command.status.success() {
         bail!("Failed with output:\n{}", String::from_utf8(command.stderr)?);
     }
@@ -49,3 +67,30 @@ pub fn run_command(cmd: &str) -> Result<DynVal> {
     let output = output.trim_matches('\n');
     Ok(DynVal::from(output))
 }
+
+/// Interpret the output of the script of a variable according to the `:format` of that variable.
+pub fn parse_output(output: &str, format: VarFormat) -> Result<DynVal> {
+    match format {
+        VarFormat::Raw => Ok(DynVal::from(output)),
+        VarFormat::Json => {
+            let value: serde_json::Value =
+                serde_json::from_str(output).map_err(|e| anyhow!("Output is not valid JSON: {}", e))?;
+            Ok(DynVal::from(&value))
+        }
+        VarFormat::Lines => Ok(DynVal::from(&serde_json::Value::from(output.lines().collect::<Vec<_>>()))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        assert_eq!(parse_output("[1, 2]", VarFormat::Raw).unwrap(), DynVal::from("[1, 2]"));
+        assert_eq!(parse_output(r#"{"a": [1, 2]}"#, VarFormat::Json).unwrap(), DynVal::from(r#"{"a":[1,2]}"#));
+        assert_eq!(parse_output(r#""text""#, VarFormat::Json).unwrap(), DynVal::from("text"));
+        assert!(parse_output("{not json", VarFormat::Json).is_err());
+        assert_eq!(parse_output("a\nb c\n", VarFormat::Lines).unwrap(), DynVal::from(r#"["a","b c"]"#));
+    }
+}
@@ -1,9 +1,16 @@
 use crate::util::IterAverage;
 use anyhow::{Context, Result};
+use futures_util::StreamExt;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use std::{fs::read_to_string, sync::Mutex};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    sync::Mutex,
+    time::Duration,
+};
 use sysinfo::{ComponentExt, DiskExt, NetworkExt, NetworksExt, ProcessorExt, System, SystemExt};
+use zbus::zvariant::{OwnedValue, Value};
 
 static SYSTEM: Lazy<Mutex<System>> = Lazy::new(|| Mutex::new(System::new()));
 
@@ -170,3 +177,185 @@ pub fn net() -> String {
     );
     interfaces
 }
+
+const MPRIS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const DBUS_PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+/// Interval in which the position of playing MPRIS players is read, as players don't signal changes of their position.
+const MPRIS_POSITION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An MPRIS media player, along with the unique bus name of the connection that owns it.
+struct MprisPlayer {
+    owner: String,
+    state: serde_json::Value,
+}
+
+/// Keep track of all MPRIS media players, calling `on_change` with the new state whenever it changes.
+/// The currently playing player (or the first one, if none is playing) is the active player. The state is `null` if
+/// there is no player.
+/// Players are updated when they signal a change of their properties, and added or removed when they appear on or
+/// disappear from the bus. Only the position of playing players is polled.
+pub async fn watch_mpris(mut on_change: impl FnMut(String) -> Result<()>) -> Result<()> {
+    let connection = zbus::Connection::session().await.context("Failed to connect to the DBus session bus")?;
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    let mut owner_changes = dbus.receive_name_owner_changed().await?;
+    let mut messages = zbus::MessageStream::from(&connection);
+    dbus.add_match(&format!(
+        "type='signal',interface='{}',member='PropertiesChanged',path='{}'",
+        DBUS_PROPERTIES_INTERFACE, MPRIS_PATH
+    ))
+    .await?;
+
+    let mut players = list_mpris_players(&connection, &dbus).await?;
+    let mut position_interval = tokio::time::interval(MPRIS_POSITION_INTERVAL);
+    let mut last_output = None;
+    loop {
+        let output = mpris_output(&players);
+        if last_output.as_ref() != Some(&output) {
+            on_change(output.clone())?;
+            last_output = Some(output);
+        }
+
+        tokio::select! {
+            Some(message) = messages.next() => {
+                let message = message?;
+                let header = message.header()?;
+                let is_properties_changed = header.interface()?.map_or(false, |x| x.as_str() == DBUS_PROPERTIES_INTERFACE)
+                    && header.member()?.map_or(false, |x| x.as_str() == "PropertiesChanged");
+                let sender = match header.sender()? {
+                    Some(sender) if is_properties_changed => sender.to_string(),
+                    _ => continue,
+                };
+                for (name, player) in players.iter_mut().filter(|(_, player)| player.owner == sender) {
+                    match get_mpris_player(&connection, name).await {
+                        Ok(state) => player.state = state,
+                        Err(err) => log::debug!("Failed to query {}: {:?}", name, err),
+                    }
+                }
+            }
+            Some(signal) = owner_changes.next() => {
+                let args = signal.args()?;
+                let name = args.name().to_string();
+                if !name.starts_with(MPRIS_NAME_PREFIX) {
+                    continue;
+                }
+                players.remove(&name);
+                if let Some(owner) = args.new_owner().as_ref() {
+                    add_mpris_player(&connection, &mut players, name, owner.to_string()).await;
+                }
+            }
+            _ = position_interval.tick() => {
+                for (name, player) in players.iter_mut().filter(|(_, player)| player.state["status"] == "Playing") {
+                    match get_mpris_position(&connection, name).await {
+                        Ok(position) => player.state["position"] = position.into(),
+                        Err(err) => log::debug!("Failed to query the position of {}: {:?}", name, err),
+                    }
+                }
+            }
+            else => anyhow::bail!("Stopped receiving messages from DBus"),
+        }
+    }
+}
+
+/// Get the current state of the MPRIS media players once, in the same format as [`watch_mpris`].
+pub async fn get_mpris() -> Result<String> {
+    let connection = zbus::Connection::session().await.context("Failed to connect to the DBus session bus")?;
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    Ok(mpris_output(&list_mpris_players(&connection, &dbus).await?))
+}
+
+/// Query all MPRIS media players that are currently on the bus.
+async fn list_mpris_players(
+    connection: &zbus::Connection,
+    dbus: &zbus::fdo::DBusProxy<'_>,
+) -> Result<BTreeMap<String, MprisPlayer>> {
+    let mut players = BTreeMap::new();
+    for name in dbus.list_names().await? {
+        if name.starts_with(MPRIS_NAME_PREFIX) {
+            let owner = dbus.get_name_owner(name.as_str().try_into()?).await?.to_string();
+            add_mpris_player(connection, &mut players, name.to_string(), owner).await;
+        }
+    }
+    Ok(players)
+}
+
+async fn add_mpris_player(
+    connection: &zbus::Connection,
+    players: &mut BTreeMap<String, MprisPlayer>,
+    name: String,
+    owner: String,
+) {
+    match get_mpris_player(connection, &name).await {
+        Ok(state) => {
+            players.insert(name, MprisPlayer { owner, state });
+        }
+        Err(err) => log::debug!("Failed to query {}: {:?}", name, err),
+    }
+}
+
+fn mpris_output(players: &BTreeMap<String, MprisPlayer>) -> String {
+    let states = players.values().map(|player| player.state.clone()).collect_vec();
+    let active_player = states.iter().find(|player| player["status"] == "Playing").or_else(|| states.first());
+    match active_player {
+        Some(active_player) => {
+            let mut result = active_player.clone();
+            result["players"] = serde_json::Value::Array(states.clone());
+            result.to_string()
+        }
+        None => "null".to_string(),
+    }
+}
+
+async fn get_mpris_player(connection: &zbus::Connection, name: &str) -> Result<serde_json::Value> {
+    let reply = connection
+        .call_method(Some(name), MPRIS_PATH, Some(DBUS_PROPERTIES_INTERFACE), "GetAll", &(MPRIS_PLAYER_INTERFACE,))
+        .await?;
+    let properties: HashMap<String, OwnedValue> = reply.body()?;
+    let status = match properties.get("PlaybackStatus").map(|x| &**x) {
+        Some(Value::Str(x)) => x.to_string(),
+        _ => String::new(),
+    };
+    let metadata: HashMap<String, OwnedValue> = match properties.get("Metadata") {
+        Some(metadata) => HashMap::try_from(metadata.clone()).unwrap_or_default(),
+        None => HashMap::new(),
+    };
+
+    let metadata_string = |key: &str| match metadata.get(key).map(|x| &**x) {
+        Some(Value::Str(x)) => x.to_string(),
+        Some(Value::Array(values)) => {
+            values.get().iter().filter_map(|x| if let Value::Str(x) = x { Some(x.as_str()) } else { None }).join(", ")
+        }
+        _ => String::new(),
+    };
+
+    Ok(serde_json::json!({
+        "player": name.trim_start_matches(MPRIS_NAME_PREFIX),
+        "status": status,
+        "title": metadata_string("xesam:title"),
+        "artist": metadata_string("xesam:artist"),
+        "album": metadata_string("xesam:album"),
+        "art_url": metadata_string("mpris:artUrl"),
+        // Not all players support reporting their position
+        "position": mpris_microseconds_to_secs(properties.get("Position").map(|x| &**x)),
+        "length": mpris_microseconds_to_secs(metadata.get("mpris:length").map(|x| &**x)),
+    }))
+}
+
+async fn get_mpris_position(connection: &zbus::Connection, name: &str) -> Result<f64> {
+    let reply = connection
+        .call_method(Some(name), MPRIS_PATH, Some(DBUS_PROPERTIES_INTERFACE), "Get", &(MPRIS_PLAYER_INTERFACE, "Position"))
+        .await?;
+    let position: OwnedValue = reply.body()?;
+    Ok(mpris_microseconds_to_secs(Some(&*position)))
+}
+
+fn mpris_microseconds_to_secs(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::I64(x)) => *x as f64 / 1_000_000f64,
+        Some(Value::U64(x)) => *x as f64 / 1_000_000f64,
+        Some(Value::Value(x)) => mpris_microseconds_to_secs(Some(x)),
+        _ => 0f64,
+    }
+}
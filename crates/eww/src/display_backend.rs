@@ -2,11 +2,16 @@ pub use platform::*;
 
 #[cfg(not(any(feature = "x11", feature = "wayland")))]
 mod platform {
+    use gtk::prelude::*;
     use yuck::config::window_definition::{WindowDefinition, WindowStacking};
 
     pub fn initialize_window(_window_def: &WindowDefinition, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
         Some(gtk::Window::new(gtk::WindowType::Toplevel))
     }
+
+    pub fn raise_window(window: &gtk::Window) {
+        window.present();
+    }
 }
 
 #[cfg(feature = "wayland")]
@@ -22,6 +27,9 @@ mod platform {
         let window = gtk::Window::new(gtk::WindowType::Toplevel);
         // Initialising a layer shell surface
         gtk_layer_shell::init_for_window(&window);
+        // Use the window name as the namespace by default, so compositor rules can target specific windows
+        let namespace = window_def.backend_options.namespace.as_deref().unwrap_or(&window_def.name);
+        gtk_layer_shell::set_namespace(&window, namespace);
         // Sets the monitor where the surface is shown
         match window_def.monitor_number {
             Some(index) => {
@@ -46,47 +54,69 @@ mod platform {
         // Sets the keyboard interactivity
         gtk_layer_shell::set_keyboard_interactivity(&window, window_def.backend_options.focusable);
 
-        if let Some(geometry) = window_def.geometry {
-            // Positioning surface
-            let mut top = false;
-            let mut left = false;
-            let mut right = false;
-            let mut bottom = false;
-
-            match geometry.anchor_point.x {
-                AnchorAlignment::START => left = true,
-                AnchorAlignment::CENTER => {}
-                AnchorAlignment::END => right = true,
-            }
-            match geometry.anchor_point.y {
-                AnchorAlignment::START => top = true,
-                AnchorAlignment::CENTER => {}
-                AnchorAlignment::END => bottom = true,
-            }
+        apply_geometry(&window, window_def, monitor);
+        if window_def.backend_options.exclusive {
+            gtk_layer_shell::auto_exclusive_zone_enable(&window);
+        }
+        Some(window)
+    }
 
-            gtk_layer_shell::set_anchor(&window, gtk_layer_shell::Edge::Left, left);
-            gtk_layer_shell::set_anchor(&window, gtk_layer_shell::Edge::Right, right);
-            gtk_layer_shell::set_anchor(&window, gtk_layer_shell::Edge::Top, top);
-            gtk_layer_shell::set_anchor(&window, gtk_layer_shell::Edge::Bottom, bottom);
+    /// Move the layer shell surface to the output of the given monitor.
+    /// gtk-layer-shell remaps the surface to move it, which creates a new layer surface on that output.
+    pub fn move_window_to_monitor(window: &gtk::Window, window_def: &WindowDefinition, monitor: gdk::Rectangle) -> Option<()> {
+        let index = window_def.monitor_number?;
+        let gdk_monitor = gdk::Display::default().expect("could not get default display").monitor(index)?;
+        gtk_layer_shell::set_monitor(window, &gdk_monitor);
+        apply_geometry(window, window_def, monitor);
+        Some(())
+    }
 
-            let xoffset = geometry.offset.x.pixels_relative_to(monitor.width);
-            let yoffset = geometry.offset.y.pixels_relative_to(monitor.height);
+    /// Layer shell surfaces can't be raised, their order is only determined by the layer given by their `:stacking`.
+    pub fn raise_window(_window: &gtk::Window) {
+        log::warn!("Raising windows is not supported on wayland, use :stacking to put the window on a higher layer instead");
+    }
 
-            if left {
-                gtk_layer_shell::set_margin(&window, gtk_layer_shell::Edge::Left, xoffset);
-            } else {
-                gtk_layer_shell::set_margin(&window, gtk_layer_shell::Edge::Right, xoffset);
-            }
-            if bottom {
-                gtk_layer_shell::set_margin(&window, gtk_layer_shell::Edge::Bottom, yoffset);
-            } else {
-                gtk_layer_shell::set_margin(&window, gtk_layer_shell::Edge::Top, yoffset);
-            }
+    /// Anchor the surface and set its margins according to the geometry of the window, relative to the given monitor.
+    fn apply_geometry(window: &gtk::Window, window_def: &WindowDefinition, monitor: gdk::Rectangle) {
+        let geometry = match window_def.geometry {
+            Some(geometry) => geometry,
+            None => return,
+        };
+        // Positioning surface
+        let mut top = false;
+        let mut left = false;
+        let mut right = false;
+        let mut bottom = false;
+
+        match geometry.anchor_point.x {
+            AnchorAlignment::START => left = true,
+            AnchorAlignment::CENTER => {}
+            AnchorAlignment::END => right = true,
         }
-        if window_def.backend_options.exclusive {
-            gtk_layer_shell::auto_exclusive_zone_enable(&window);
+        match geometry.anchor_point.y {
+            AnchorAlignment::START => top = true,
+            AnchorAlignment::CENTER => {}
+            AnchorAlignment::END => bottom = true,
+        }
+
+        gtk_layer_shell::set_anchor(window, gtk_layer_shell::Edge::Left, left);
+        gtk_layer_shell::set_anchor(window, gtk_layer_shell::Edge::Right, right);
+        gtk_layer_shell::set_anchor(window, gtk_layer_shell::Edge::Top, top);
+        gtk_layer_shell::set_anchor(window, gtk_layer_shell::Edge::Bottom, bottom);
+
+        let xoffset = geometry.offset.x.pixels_relative_to(monitor.width);
+        let yoffset = geometry.offset.y.pixels_relative_to(monitor.height);
+
+        if left {
+            gtk_layer_shell::set_margin(window, gtk_layer_shell::Edge::Left, xoffset);
+        } else {
+            gtk_layer_shell::set_margin(window, gtk_layer_shell::Edge::Right, xoffset);
+        }
+        if bottom {
+            gtk_layer_shell::set_margin(window, gtk_layer_shell::Edge::Bottom, yoffset);
+        } else {
+            gtk_layer_shell::set_margin(window, gtk_layer_shell::Edge::Top, yoffset);
         }
-        Some(window)
     }
 }
 
@@ -111,9 +141,11 @@ mod platform {
     pub fn initialize_window(window_def: &WindowDefinition, _monitor: gdk::Rectangle) -> Option<gtk::Window> {
         let window_type = if window_def.backend_options.wm_ignore { gtk::WindowType::Popup } else { gtk::WindowType::Toplevel };
         let window = gtk::Window::new(window_type);
-        let wm_class_name = format!("eww-{}", window_def.name);
+        // The instance name is the window name by default, so window manager rules can target specific windows
+        let wm_instance = window_def.backend_options.wm_instance.clone().unwrap_or_else(|| window_def.name.clone());
+        let wm_class = window_def.backend_options.wm_class.clone().unwrap_or_else(|| format!("eww-{}", window_def.name));
         #[allow(deprecated)]
-        window.set_wmclass(&wm_class_name, &wm_class_name);
+        window.set_wmclass(&wm_instance, &wm_class);
         window.set_resizable(window_def.resizable);
         window.set_keep_above(window_def.stacking == WindowStacking::Foreground);
         window.set_keep_below(window_def.stacking == WindowStacking::Background);
@@ -125,6 +157,13 @@ mod platform {
         Some(window)
     }
 
+    pub fn raise_window(window: &gtk::Window) {
+        match window.window() {
+            Some(gdk_window) => gdk_window.raise(),
+            None => log::warn!("Failed to raise window, as it has not been realized yet"),
+        }
+    }
+
     pub fn set_xprops(window: &gtk::Window, monitor: gdk::Rectangle, window_def: &WindowDefinition) -> Result<()> {
         let backend = X11Backend::new()?;
         backend.set_xprops_for(window, monitor, window_def)?;
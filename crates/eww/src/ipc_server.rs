@@ -2,7 +2,7 @@ use crate::{app, opts};
 use anyhow::{Context, Result};
 use std::time::Duration;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
     sync::mpsc::*,
 };
 
@@ -33,6 +33,10 @@ async fn handle_connection(mut stream: tokio::net::UnixStream, evt_send: Unbound
 
     log::debug!("received command from IPC: {:?}", &action);
 
+    if let opts::ActionWithServer::UpdateStream = action {
+        return handle_update_stream(stream_read, evt_send).await;
+    }
+
     let (command, maybe_response_recv) = action.into_daemon_command();
 
     evt_send.send(command)?;
@@ -49,16 +53,48 @@ async fn handle_connection(mut stream: tokio::net::UnixStream, evt_send: Unbound
     Ok(())
 }
 
+/// Apply the `variable_name=new_value` lines sent over an update stream until the client closes the connection.
+/// Lines that have already been received together are applied as a single update,
+/// such that a client sending updates faster than they can be rendered doesn't cause a backlog of redraws.
+async fn handle_update_stream(
+    stream_read: tokio::net::unix::ReadHalf<'_>,
+    evt_send: UnboundedSender<app::DaemonCommand>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream_read);
+    let mut line = String::new();
+    let mut mappings = Vec::new();
+    loop {
+        line.clear();
+        let bytes_read = reader.read_line(&mut line).await.context("Failed to read update from IPC stream")?;
+        let update = line.trim_end_matches('\n');
+        if !update.is_empty() {
+            match opts::parse_var_update_arg(update) {
+                Ok(mapping) => mappings.push(mapping),
+                Err(err) => log::warn!("Ignoring invalid update in update stream: {}", err),
+            }
+        }
+        // Keep collecting updates while further complete lines have already been received
+        if bytes_read > 0 && reader.buffer().contains(&b'\n') {
+            continue;
+        }
+        if !mappings.is_empty() {
+            evt_send.send(app::DaemonCommand::UpdateVars(std::mem::take(&mut mappings)))?;
+        }
+        if bytes_read == 0 {
+            return Ok(());
+        }
+    }
+}
+
 /// Read a single message from a unix stream, and parses it into a `ActionWithServer`
 /// The format here requires the first 4 bytes to be the size of the rest of the message (in big-endian), followed by the rest of the message.
 async fn read_action_from_stream(stream_read: &'_ mut tokio::net::unix::ReadHalf<'_>) -> Result<opts::ActionWithServer> {
     let mut message_byte_length = [0u8; 4];
     stream_read.read_exact(&mut message_byte_length).await.context("Failed to read message size header in IPC message")?;
     let message_byte_length = u32::from_be_bytes(message_byte_length);
-    let mut raw_message = Vec::<u8>::with_capacity(message_byte_length as usize);
-    while raw_message.len() < message_byte_length as usize {
-        stream_read.read_buf(&mut raw_message).await.context("Failed to read actual IPC message")?;
-    }
+    // Read exactly the size of the message, as an update stream continues with further data after it
+    let mut raw_message = vec![0u8; message_byte_length as usize];
+    stream_read.read_exact(&mut raw_message).await.context("Failed to read actual IPC message")?;
 
     bincode::deserialize(&raw_message).context("Failed to parse client message")
 }
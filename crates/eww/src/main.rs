@@ -50,7 +50,9 @@ fn main() {
     if std::env::var("RUST_LOG").is_ok() {
         pretty_env_logger::init_timed();
     } else {
-        pretty_env_logger::formatted_timed_builder().filter(Some("eww"), log_level_filter).init();
+        // Let all eww logs through the logger, so that `eww log-level` can change the level at runtime via the max level.
+        pretty_env_logger::formatted_timed_builder().filter(Some("eww"), log::LevelFilter::Trace).init();
+        log::set_max_level(log_level_filter);
     }
 
     let result: Result<()> = try {
@@ -102,6 +104,12 @@ fn main() {
                 false
             }
 
+            opts::Action::WithServer(ActionWithServer::UpdateStream) => {
+                let mut stream = attempt_connect(&paths.get_ipc_socket_file(), 5).context("Failed to connect to daemon")?;
+                client::do_update_stream(&mut stream, std::io::stdin().lock())?;
+                false
+            }
+
             // a running daemon is necessary for this command
             opts::Action::WithServer(action) => {
                 // attempt to just send the command to a running daemon
@@ -197,6 +205,7 @@ pub struct EwwPaths {
     log_file: PathBuf,
     ipc_socket_file: PathBuf,
     config_dir: PathBuf,
+    poll_cache_file: PathBuf,
 }
 
 impl EwwPaths {
@@ -228,23 +237,46 @@ impl EwwPaths {
             log::warn!("The IPC socket file's absolute path exceeds 100 bytes, the socket may fail to create.");
         }
 
+        let cache_dir = std::env::var("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".cache"));
+
         Ok(EwwPaths {
             config_dir,
-            log_file: std::env::var("XDG_CACHE_HOME")
-                .map(PathBuf::from)
-                .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".cache"))
-                .join(format!("eww_{}.log", daemon_id)),
+            log_file: cache_dir.join(format!("eww_{}.log", daemon_id)),
+            poll_cache_file: cache_dir.join(format!("eww_{}_poll_cache.json", daemon_id)),
             ipc_socket_file,
         })
     }
 
+    /// Find the configuration directory in the default locations, checking `$XDG_CONFIG_HOME/eww` first,
+    /// and falling back to `~/.config/eww`. A directory containing an `eww.yuck` file is preferred over one that doesn't.
+    /// Directories given via `--config` take precedence over these, and are handled by [`Self::from_config_dir`].
     pub fn default() -> Result<Self> {
-        let config_dir = std::env::var("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap()).join(".config"))
-            .join("eww");
+        let mut candidates = Vec::new();
+        if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
+            candidates.push(PathBuf::from(xdg_config_home).join("eww"));
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            let home_config_dir = PathBuf::from(home).join(".config").join("eww");
+            if !candidates.contains(&home_config_dir) {
+                candidates.push(home_config_dir);
+            }
+        }
 
-        Self::from_config_dir(config_dir)
+        let config_dir =
+            candidates.iter().find(|dir| dir.join("eww.yuck").is_file()).or_else(|| candidates.iter().find(|dir| dir.is_dir()));
+        match config_dir {
+            Some(config_dir) => {
+                log::debug!("Resolved configuration directory to {}", config_dir.display());
+                Self::from_config_dir(config_dir)
+            }
+            None => bail!(
+                "Could not find a configuration directory. Checked the following locations:\n{}\nUse --config to specify the \
+                 path to your configuration directory.",
+                candidates.iter().map(|dir| format!("  - {}", dir.display())).collect::<Vec<_>>().join("\n")
+            ),
+        }
     }
 
     pub fn get_log_file(&self) -> &Path {
@@ -255,6 +287,11 @@ impl EwwPaths {
         self.ipc_socket_file.as_path()
     }
 
+    /// Get the file the values of polling variables with `:cache` are stored in.
+    pub fn get_poll_cache_file(&self) -> &Path {
+        self.poll_cache_file.as_path()
+    }
+
     pub fn get_config_dir(&self) -> &Path {
         self.config_dir.as_path()
     }
@@ -79,8 +79,16 @@ pub enum ActionWithServer {
         /// variable_name="new_value"-pairs that will be updated
         #[structopt(parse(try_from_str = parse_var_update_arg))]
         mappings: Vec<(VarName, DynVal)>,
+
+        /// Revert the variables to their previous values after the given duration (i.e.: 500ms)
+        #[structopt(long = "for", parse(try_from_str = parse_duration))]
+        revert_after: Option<std::time::Duration>,
     },
 
+    /// Continuously update variables in a running eww instance, reading variable_name="new_value"-pairs line by line from stdin
+    #[structopt(name = "update-stream")]
+    UpdateStream,
+
     /// Open the GTK debugger
     #[structopt(name = "inspector", alias = "debugger")]
     OpenInspector,
@@ -123,6 +131,11 @@ pub enum ActionWithServer {
         should_toggle: bool,
     },
 
+    /// Raise an open window above the other windows.
+    /// Not supported on wayland, where the order of windows is only determined by their :stacking.
+    #[structopt(name = "raise")]
+    RaiseWindow { window_name: String },
+
     /// Close the given windows
     #[structopt(name = "close", alias = "c")]
     CloseWindows { windows: Vec<String> },
@@ -147,7 +160,9 @@ pub enum ActionWithServer {
         all: bool,
     },
 
-    /// Get the value of a variable if defined
+    /// Get the value of a variable if defined.
+    /// Magic variables that are currently not in use are sampled once to provide an up to date value.
+    /// For rates such as EWW_CPU and EWW_NET, this takes one polling interval.
     #[structopt(name = "get")]
     GetVar { name: String },
 
@@ -165,6 +180,47 @@ pub enum ActionWithServer {
     /// Print out the scope graph structure in graphviz dot format.
     #[structopt(name = "graph")]
     ShowGraph,
+
+    /// Print the values the arguments of a custom widget in an open window resolved to, as a json object.
+    #[structopt(name = "widget-args")]
+    ShowWidgetArgs {
+        /// Name of the window the widget is used in
+        window_name: String,
+
+        /// Name of the custom widget.
+        /// If the widget is used multiple times in the window, the scope index of the invocation, as shown by `eww graph`.
+        widget: String,
+    },
+
+    /// Pause the scripts of the given `defpoll`, `deflisten` and `defdbus` variables. The variables keep their last value.
+    /// The process of a paused `deflisten` variable is killed, and started again when the variable is resumed.
+    /// Reloading the configuration resumes all paused variables.
+    #[structopt(name = "pause-var")]
+    PauseVars { names: Vec<String> },
+
+    /// Resume the scripts of the given variables, which have been paused via `eww pause-var`.
+    #[structopt(name = "resume-var")]
+    ResumeVars { names: Vec<String> },
+
+    /// Print the position and size of the widget with the given `:id` in an open window, in screen coordinates, as a json object.
+    /// The widget has to be visible. This is not supported on wayland.
+    #[structopt(name = "widget-geometry")]
+    WidgetGeometry {
+        /// Name of the window the widget is used in
+        window_name: String,
+
+        /// The `:id` of the widget
+        widget_id: String,
+    },
+
+    /// Change the log level of the running eww daemon, or print the current log level if none is given.
+    /// Possible levels are off, error, warn, info, debug and trace.
+    /// The level can not be changed if the daemon was started with `RUST_LOG` set.
+    #[structopt(name = "log-level")]
+    LogLevel {
+        #[structopt(parse(try_from_str = parse_log_level))]
+        level: Option<String>,
+    },
 }
 
 impl Opt {
@@ -181,13 +237,23 @@ impl From<RawOpt> for Opt {
     }
 }
 
-fn parse_var_update_arg(s: &str) -> Result<(VarName, DynVal)> {
+pub fn parse_var_update_arg(s: &str) -> Result<(VarName, DynVal)> {
     let (name, value) = s
         .split_once('=')
         .with_context(|| format!("arguments must be in the shape `variable_name=\"new_value\"`, but got: {}", s))?;
     Ok((name.into(), DynVal::from_string(value.to_owned())))
 }
 
+fn parse_log_level(s: &str) -> Result<String> {
+    let level: log::LevelFilter =
+        s.parse().with_context(|| format!("Invalid log level `{}`, must be one of off, error, warn, info, debug, trace", s))?;
+    Ok(level.to_string().to_lowercase())
+}
+
+fn parse_duration(s: &str) -> Result<std::time::Duration> {
+    Ok(DynVal::from_string(s.to_owned()).as_duration()?)
+}
+
 impl ActionWithServer {
     pub fn can_start_daemon(&self) -> bool {
         matches!(self, ActionWithServer::OpenWindow { .. } | ActionWithServer::OpenMany { .. })
@@ -195,7 +261,12 @@ impl ActionWithServer {
 
     pub fn into_daemon_command(self) -> (app::DaemonCommand, Option<daemon_response::DaemonResponseReceiver>) {
         let command = match self {
-            ActionWithServer::Update { mappings } => app::DaemonCommand::UpdateVars(mappings),
+            ActionWithServer::Update { mappings, revert_after: None } => app::DaemonCommand::UpdateVars(mappings),
+            ActionWithServer::Update { mappings, revert_after: Some(duration) } => {
+                app::DaemonCommand::UpdateVarsTemporarily { mappings, duration }
+            }
+            // The updates of a stream are read from the connection by the IPC server itself
+            ActionWithServer::UpdateStream => app::DaemonCommand::NoOp,
             ActionWithServer::OpenInspector => app::DaemonCommand::OpenInspector,
 
             ActionWithServer::KillServer => app::DaemonCommand::KillServer,
@@ -232,6 +303,30 @@ impl ActionWithServer {
             }
             ActionWithServer::ShowDebug => return with_response_channel(app::DaemonCommand::PrintDebug),
             ActionWithServer::ShowGraph => return with_response_channel(app::DaemonCommand::PrintGraph),
+            ActionWithServer::ShowWidgetArgs { window_name, widget } => {
+                return with_response_channel(|sender| app::DaemonCommand::PrintWidgetArgs { window_name, widget, sender })
+            }
+            ActionWithServer::RaiseWindow { window_name } => {
+                return with_response_channel(|sender| app::DaemonCommand::RaiseWindow { window_name, sender })
+            }
+            ActionWithServer::PauseVars { names } => {
+                return with_response_channel(|sender| app::DaemonCommand::PauseVars {
+                    names: names.into_iter().map(VarName).collect(),
+                    sender,
+                })
+            }
+            ActionWithServer::ResumeVars { names } => {
+                return with_response_channel(|sender| app::DaemonCommand::ResumeVars {
+                    names: names.into_iter().map(VarName).collect(),
+                    sender,
+                })
+            }
+            ActionWithServer::WidgetGeometry { window_name, widget_id } => {
+                return with_response_channel(|sender| app::DaemonCommand::GetWidgetGeometry { window_name, widget_id, sender })
+            }
+            ActionWithServer::LogLevel { level } => {
+                return with_response_channel(|sender| app::DaemonCommand::SetLogLevel { level, sender })
+            }
         };
         (command, None)
     }
@@ -1,13 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+};
 
 use crate::{
     app,
-    config::{create_script_var_failed_warn, script_var},
+    config::{create_script_var_failed_warn, inbuilt, poll_cache, script_var, system_stats},
+    daemon_response::DaemonResponseSender,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use app::DaemonCommand;
 
 use eww_shared_util::VarName;
+use futures_util::StreamExt;
 use nix::{
     sys::signal,
     unistd::{setpgid, Pid},
@@ -18,25 +23,43 @@ use tokio::{
     sync::mpsc::UnboundedSender,
 };
 use tokio_util::sync::CancellationToken;
-use yuck::config::script_var_definition::{ListenScriptVar, PollScriptVar, ScriptVarDefinition, VarSource};
+use yuck::config::script_var_definition::{
+    BuiltinListenSource, DbusBus, DbusMember, DbusScriptVar, ListenScriptVar, ListenSource, PollScriptVar, ScriptVarDefinition,
+    VarFormat, VarSource,
+};
+use zbus::zvariant::{OwnedValue, Structure, Value};
+
+/// Time to wait before reconnecting a DBus variable after its connection failed.
+const DBUS_RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// Initialize the script var handler, and return a handle to that handler, which can be used to control
 /// the script var execution.
-pub fn init(evt_send: UnboundedSender<DaemonCommand>) -> ScriptVarHandlerHandle {
+/// The values of polling variables with `:cache` are stored in the given `poll_cache_file`.
+pub fn init(evt_send: UnboundedSender<DaemonCommand>, poll_cache_file: PathBuf) -> ScriptVarHandlerHandle {
     let (msg_send, mut msg_recv) = tokio::sync::mpsc::unbounded_channel();
-    let handle = ScriptVarHandlerHandle { msg_send };
-    std::thread::spawn(move || {
+    let thread = std::thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().expect("Failed to initialize tokio runtime for script var handlers");
         rt.block_on(async {
             let _: Result<_> = try {
                 let mut handler = ScriptVarHandler {
                     listen_handler: ListenVarHandler::new(evt_send.clone())?,
-                    poll_handler: PollVarHandler::new(evt_send)?,
+                    dbus_handler: DbusVarHandler::new(evt_send.clone())?,
+                    poll_handler: PollVarHandler::new(evt_send, poll_cache_file)?,
                 };
                 crate::loop_select_exiting! {
                     Some(msg) = msg_recv.recv() => match msg {
-                        ScriptVarHandlerMsg::AddVar(var) => {
-                            handler.add(var).await;
+                        ScriptVarHandlerMsg::AddVar(var, cwd) => {
+                            handler.add(var, cwd).await;
+                        }
+                        ScriptVarHandlerMsg::Sample(var, sender) => {
+                            tokio::spawn(async move {
+                                let name = var.name().clone();
+                                let response = match sample_magic_var(var).await {
+                                    Ok(value) => sender.send_success(value.to_string()),
+                                    Err(err) => sender.send_failure(format!("Failed to sample variable \"{}\": {}", name, err)),
+                                };
+                                crate::print_result_err!("while responding with a sampled value", response);
+                            });
                         }
                         ScriptVarHandlerMsg::Stop(name) => {
                             handler.stop_for_variable(&name)?;
@@ -51,20 +74,29 @@ pub fn init(evt_send: UnboundedSender<DaemonCommand>) -> ScriptVarHandlerHandle
             };
         })
     });
-    handle
+    ScriptVarHandlerHandle { msg_send, thread: Some(thread) }
 }
 
 /// Handle to the script-var handling system.
 pub struct ScriptVarHandlerHandle {
     msg_send: UnboundedSender<ScriptVarHandlerMsg>,
+    thread: Option<std::thread::JoinHandle<()>>,
 }
 
 impl ScriptVarHandlerHandle {
-    /// Add a new script-var that should be executed.
-    pub fn add(&self, script_var: ScriptVarDefinition) {
+    /// Add a new script-var that should be executed, running its script in the given working directory.
+    pub fn add(&self, script_var: ScriptVarDefinition, cwd: Option<String>) {
+        crate::print_result_err!(
+            "while forwarding instruction to script-var handler",
+            self.msg_send.send(ScriptVarHandlerMsg::AddVar(script_var, cwd))
+        );
+    }
+
+    /// Sample the value of a magic variable that is not running, without starting it, and respond with it to the sender.
+    pub fn sample(&self, script_var: ScriptVarDefinition, sender: DaemonResponseSender) {
         crate::print_result_err!(
             "while forwarding instruction to script-var handler",
-            self.msg_send.send(ScriptVarHandlerMsg::AddVar(script_var))
+            self.msg_send.send(ScriptVarHandlerMsg::Sample(script_var, sender))
         );
     }
 
@@ -83,12 +115,23 @@ impl ScriptVarHandlerHandle {
             self.msg_send.send(ScriptVarHandlerMsg::StopAll)
         );
     }
+
+    /// Stop the execution of all script-vars, and wait until the handler has shut down,
+    /// killing the processes of all running scripts.
+    /// This should be used before exiting, as otherwise these processes may be left behind.
+    pub fn stop_all_and_wait(&mut self) {
+        self.stop_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
 }
 
 /// Message enum used by the ScriptVarHandlerHandle to communicate to the ScriptVarHandler
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
 enum ScriptVarHandlerMsg {
-    AddVar(ScriptVarDefinition),
+    AddVar(ScriptVarDefinition, Option<String>),
+    Sample(ScriptVarDefinition, DaemonResponseSender),
     Stop(VarName),
     StopAll,
 }
@@ -97,13 +140,18 @@ enum ScriptVarHandlerMsg {
 struct ScriptVarHandler {
     listen_handler: ListenVarHandler,
     poll_handler: PollVarHandler,
+    dbus_handler: DbusVarHandler,
 }
 
 impl ScriptVarHandler {
-    async fn add(&mut self, script_var: ScriptVarDefinition) {
+    async fn add(&mut self, script_var: ScriptVarDefinition, cwd: Option<String>) {
         match script_var {
-            ScriptVarDefinition::Poll(var) => self.poll_handler.start(var).await,
-            ScriptVarDefinition::Listen(var) => self.listen_handler.start(var).await,
+            ScriptVarDefinition::Poll(var) => self.poll_handler.start(var, cwd).await,
+            ScriptVarDefinition::Listen(var) => match var.command.clone() {
+                ListenSource::Shell(command) => self.listen_handler.start(var, command, cwd).await,
+                ListenSource::Builtin(BuiltinListenSource::Mpris) => self.dbus_handler.start_mpris(var.name).await,
+            },
+            ScriptVarDefinition::Dbus(var) => self.dbus_handler.start(var).await,
         };
     }
 
@@ -112,6 +160,7 @@ impl ScriptVarHandler {
         log::debug!("Stopping script var process for variable {}", name);
         self.listen_handler.stop_for_variable(name);
         self.poll_handler.stop_for_variable(name);
+        self.dbus_handler.stop_for_variable(name);
         Ok(())
     }
 
@@ -120,21 +169,23 @@ impl ScriptVarHandler {
         log::debug!("Stopping script-var-handlers");
         self.listen_handler.stop_all();
         self.poll_handler.stop_all();
+        self.dbus_handler.stop_all();
     }
 }
 
 struct PollVarHandler {
     evt_send: UnboundedSender<DaemonCommand>,
     poll_handles: HashMap<VarName, CancellationToken>,
+    poll_cache_file: PathBuf,
 }
 
 impl PollVarHandler {
-    fn new(evt_send: UnboundedSender<DaemonCommand>) -> Result<Self> {
-        let handler = PollVarHandler { evt_send, poll_handles: HashMap::new() };
+    fn new(evt_send: UnboundedSender<DaemonCommand>, poll_cache_file: PathBuf) -> Result<Self> {
+        let handler = PollVarHandler { evt_send, poll_handles: HashMap::new(), poll_cache_file };
         Ok(handler)
     }
 
-    async fn start(&mut self, var: PollScriptVar) {
+    async fn start(&mut self, var: PollScriptVar, cwd: Option<String>) {
         if self.poll_handles.contains_key(&var.name) {
             return;
         }
@@ -143,25 +194,24 @@ impl PollVarHandler {
         let cancellation_token = CancellationToken::new();
         self.poll_handles.insert(var.name.clone(), cancellation_token.clone());
         let evt_send = self.evt_send.clone();
+        let poll_cache_file = self.poll_cache_file.clone();
         tokio::spawn(async move {
-            let result: Result<_> = try {
-                evt_send.send(app::DaemonCommand::UpdateVars(vec![(var.name.clone(), run_poll_once(&var)?)]))?;
-            };
-            if let Err(err) = result {
-                crate::error_handling_ctx::print_error(err);
-            }
-
-            crate::loop_select_exiting! {
-                _ = cancellation_token.cancelled() => break,
-                _ = tokio::time::sleep(var.interval) => {
-                    let result: Result<_> = try {
-                        evt_send.send(app::DaemonCommand::UpdateVars(vec![(var.name.clone(), run_poll_once(&var)?)]))?;
-                    };
-
-                    if let Err(err) = result {
+            let poll = async {
+                // start out as failed, such that errors from a previous run of this variable get cleared on success.
+                let mut failed = true;
+                // A value read from the cache is replaced by the result of the first run.
+                loop {
+                    if let Err(err) = run_poll_and_report(&var, cwd.as_deref(), &evt_send, &mut failed, &poll_cache_file).await {
                         crate::error_handling_ctx::print_error(err);
                     }
+                    tokio::time::sleep(var.interval).await;
                 }
+            };
+            tokio::pin!(poll);
+            // Dropping the poll while its command is running terminates the process group of that command.
+            crate::loop_select_exiting! {
+                _ = cancellation_token.cancelled() => break,
+                _ = &mut poll => break,
             }
         });
     }
@@ -178,15 +228,69 @@ impl PollVarHandler {
     }
 }
 
-fn run_poll_once(var: &PollScriptVar) -> Result<DynVal> {
+/// Run the poll var once in the given working directory and send the result to the app.
+/// Changes in the error state of the variable are reported to the app, to be exposed via `EWW_ERRORS`.
+/// If the variable has `:cache`, its new value is stored in the `poll_cache_file`.
+async fn run_poll_and_report(
+    var: &PollScriptVar,
+    cwd: Option<&str>,
+    evt_send: &UnboundedSender<DaemonCommand>,
+    failed: &mut bool,
+    poll_cache_file: &Path,
+) -> Result<()> {
+    match run_poll_once(var, cwd).await {
+        Ok(value) => {
+            if std::mem::take(failed) {
+                evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error: None })?;
+            }
+            if var.cache {
+                if let Err(err) = poll_cache::store(poll_cache_file, &var.name, &value) {
+                    log::warn!("Failed to cache the value of {}: {:?}", var.name, err);
+                }
+            }
+            evt_send.send(DaemonCommand::UpdateVars(vec![(var.name.clone(), value)]))?;
+            Ok(())
+        }
+        Err(err) => {
+            *failed = true;
+            evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error: Some(err.to_string()) })?;
+            match &var.command {
+                VarSource::Shell(span, _) => Err(anyhow!(create_script_var_failed_warn(*span, &var.name, &err.to_string()))),
+                VarSource::Function(_) => Err(err),
+            }
+        }
+    }
+}
+
+async fn run_poll_once(var: &PollScriptVar, cwd: Option<&str>) -> Result<DynVal> {
     match &var.command {
-        VarSource::Shell(span, command) => {
-            script_var::run_command(command).map_err(|e| anyhow!(create_script_var_failed_warn(*span, &var.name, &e.to_string())))
+        VarSource::Shell(_, command) => {
+            let output = run_in_process_group(command, cwd).await?;
+            script_var::parse_output(&output, var.format)
         }
         VarSource::Function(x) => x().map_err(|e| anyhow!(e)),
     }
 }
 
+/// Get the current value of a magic variable.
+/// Rate-based variables are sampled twice, one polling interval apart, such that their value covers the same
+/// timespan as when they are polled.
+async fn sample_magic_var(var: ScriptVarDefinition) -> Result<DynVal> {
+    match var {
+        ScriptVarDefinition::Poll(PollScriptVar { name, command: VarSource::Function(f), interval, .. }) => {
+            if inbuilt::RATE_BASED_VAR_NAMES.contains(&name.0.as_str()) {
+                tokio::task::spawn_blocking(f).await?.map_err(|e| anyhow!(e))?;
+                tokio::time::sleep(interval).await;
+            }
+            tokio::task::spawn_blocking(f).await?.map_err(|e| anyhow!(e))
+        }
+        ScriptVarDefinition::Listen(ListenScriptVar { command: ListenSource::Builtin(BuiltinListenSource::Mpris), .. }) => {
+            Ok(DynVal::from_string(system_stats::get_mpris().await?))
+        }
+        var => bail!("{} is not a magic variable", var.name()),
+    }
+}
+
 impl Drop for PollVarHandler {
     fn drop(&mut self) {
         self.stop_all();
@@ -204,41 +308,65 @@ impl ListenVarHandler {
         Ok(handler)
     }
 
-    async fn start(&mut self, var: ListenScriptVar) {
+    async fn start(&mut self, var: ListenScriptVar, command: String, cwd: Option<String>) {
         log::debug!("starting listen-var {}", &var.name);
         let cancellation_token = CancellationToken::new();
         self.listen_process_handles.insert(var.name.clone(), cancellation_token.clone());
 
         let evt_send = self.evt_send.clone();
         tokio::spawn(async move {
-            crate::try_logging_errors!(format!("Executing listen var-command {}", &var.command) =>  {
-                let mut handle = unsafe {
-                    tokio::process::Command::new("sh")
-                    .args(&["-c", &var.command])
-                    .stdout(std::process::Stdio::piped())
-                    .stderr(std::process::Stdio::piped())
-                    .stdin(std::process::Stdio::null())
-                    .pre_exec(|| {
-                        let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
-                        Ok(())
-                    }).spawn()?
-                };
+            let result: Result<_> = try {
+                let (mut handle, process_group) = spawn_in_process_group(&command, cwd.as_deref())?;
+                // start out as failed, such that errors from a previous run of this variable get cleared on the first output.
+                let mut failed = true;
+                // the most recent lines output so far, used for the `lines` format
+                let mut recent_lines = VecDeque::new();
                 let mut stdout_lines = BufReader::new(handle.stdout.take().unwrap()).lines();
                 let mut stderr_lines = BufReader::new(handle.stderr.take().unwrap()).lines();
                 crate::loop_select_exiting! {
-                    _ = handle.wait() => break,
+                    status = handle.wait() => {
+                        match status {
+                            Ok(status) if !status.success() => {
+                                let error = Some(format!("Listen script exited with {}", status));
+                                evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error })?;
+                            }
+                            _ => {}
+                        }
+                        break;
+                    },
                     _ = cancellation_token.cancelled() => break,
                     Ok(Some(line)) = stdout_lines.next_line() => {
-                        let new_value = DynVal::from_string(line.to_owned());
-                        evt_send.send(DaemonCommand::UpdateVars(vec![(var.name.to_owned(), new_value)]))?;
+                        let new_value = match var.format {
+                            VarFormat::Raw => Ok(DynVal::from_string(line)),
+                            VarFormat::Json => script_var::parse_output(&line, VarFormat::Json),
+                            VarFormat::Lines => Ok(push_recent_line(&mut recent_lines, line, var.max_lines.unwrap_or(1))),
+                        };
+                        match new_value {
+                            Ok(new_value) => {
+                                if std::mem::take(&mut failed) {
+                                    evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error: None })?;
+                                }
+                                evt_send.send(DaemonCommand::UpdateVars(vec![(var.name.to_owned(), new_value)]))?;
+                            }
+                            Err(err) => {
+                                failed = true;
+                                let error = Some(err.to_string());
+                                evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error })?;
+                                log::error!("Invalid output of listen var {}: {}", var.name, err);
+                            }
+                        }
                     }
                     Ok(Some(line)) = stderr_lines.next_line() => {
                         log::warn!("stderr of `{}`: {}", var.name, line);
                     }
                     else => break,
                 }
-                terminate_handle(handle).await;
-            });
+                terminate_handle(handle, process_group).await;
+            };
+            if let Err(err) = result {
+                let _ = evt_send.send(DaemonCommand::SetScriptVarError { name: var.name.clone(), error: Some(err.to_string()) });
+                log::error!("Error while executing listen var-command {}: {:?}", &command, err);
+            }
         });
     }
 
@@ -254,15 +382,299 @@ impl ListenVarHandler {
     }
 }
 
+/// Add a line to the lines kept for a listen var with the `lines` format, dropping the oldest lines beyond `max_lines`.
+/// Returns the resulting value of the variable.
+fn push_recent_line(recent_lines: &mut VecDeque<String>, line: String, max_lines: usize) -> DynVal {
+    recent_lines.push_back(line);
+    while recent_lines.len() > max_lines {
+        recent_lines.pop_front();
+    }
+    DynVal::from(&serde_json::Value::from(recent_lines.iter().map(String::as_str).collect::<Vec<_>>()))
+}
+
 impl Drop for ListenVarHandler {
     fn drop(&mut self) {
         self.stop_all();
     }
 }
 
-async fn terminate_handle(mut child: tokio::process::Child) {
-    if let Some(id) = child.id() {
-        let _ = signal::killpg(Pid::from_raw(id as i32), signal::SIGTERM);
+struct DbusVarHandler {
+    evt_send: UnboundedSender<DaemonCommand>,
+    dbus_handles: HashMap<VarName, CancellationToken>,
+}
+
+impl DbusVarHandler {
+    fn new(evt_send: UnboundedSender<DaemonCommand>) -> Result<Self> {
+        let handler = DbusVarHandler { evt_send, dbus_handles: HashMap::new() };
+        Ok(handler)
+    }
+
+    async fn start(&mut self, var: DbusScriptVar) {
+        if self.dbus_handles.contains_key(&var.name) {
+            return;
+        }
+
+        log::debug!("starting dbus var {}", &var.name);
+        let cancellation_token = CancellationToken::new();
+        self.dbus_handles.insert(var.name.clone(), cancellation_token.clone());
+        let evt_send = self.evt_send.clone();
+        tokio::spawn(async move {
+            // start out as failed, such that errors from a previous run of this variable get cleared on success.
+            let mut failed = true;
+            crate::loop_select_exiting! {
+                _ = cancellation_token.cancelled() => break,
+                result = run_dbus_var(&var, &evt_send, &mut failed) => {
+                    failed = true;
+                    if !wait_for_dbus_reconnect(&var.name, &evt_send, result, &cancellation_token).await {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Start keeping the magic variable with the state of the MPRIS media players up to date.
+    async fn start_mpris(&mut self, name: VarName) {
+        if self.dbus_handles.contains_key(&name) {
+            return;
+        }
+
+        log::debug!("starting mpris var {}", &name);
+        let cancellation_token = CancellationToken::new();
+        self.dbus_handles.insert(name.clone(), cancellation_token.clone());
+        let evt_send = self.evt_send.clone();
+        tokio::spawn(async move {
+            let mut failed = true;
+            crate::loop_select_exiting! {
+                _ = cancellation_token.cancelled() => break,
+                result = system_stats::watch_mpris(|value| {
+                    send_dbus_value(&name, &evt_send, &mut failed, DynVal::from_string(value))
+                }) => {
+                    failed = true;
+                    if !wait_for_dbus_reconnect(&name, &evt_send, result, &cancellation_token).await {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    fn stop_for_variable(&mut self, name: &VarName) {
+        if let Some(token) = self.dbus_handles.remove(name) {
+            log::debug!("stopped dbus var {}", name);
+            token.cancel();
+        }
+    }
+
+    fn stop_all(&mut self) {
+        self.dbus_handles.drain().for_each(|(_, token)| token.cancel());
+    }
+}
+
+impl Drop for DbusVarHandler {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+/// Report the error that ended the connection of a DBus variable, and wait until it should reconnect.
+/// Returns `false` if the variable has been stopped in the meantime.
+async fn wait_for_dbus_reconnect(
+    name: &VarName,
+    evt_send: &UnboundedSender<DaemonCommand>,
+    result: Result<()>,
+    cancellation_token: &CancellationToken,
+) -> bool {
+    let err = result.err().unwrap_or_else(|| anyhow!("The DBus connection was closed"));
+    let _ = evt_send.send(DaemonCommand::SetScriptVarError { name: name.clone(), error: Some(err.to_string()) });
+    log::error!("Error in DBus variable {}, reconnecting in {:?}: {:?}", name, DBUS_RECONNECT_INTERVAL, err);
+    tokio::select! {
+        _ = cancellation_token.cancelled() => false,
+        _ = tokio::time::sleep(DBUS_RECONNECT_INTERVAL) => true,
+    }
+}
+
+/// Connect to the bus of the given variable and keep it updated.
+/// While the service the variable reads from is not on the bus, this waits for it to appear,
+/// and subscribes again whenever the service is restarted.
+async fn run_dbus_var(var: &DbusScriptVar, evt_send: &UnboundedSender<DaemonCommand>, failed: &mut bool) -> Result<()> {
+    let connection = match var.bus {
+        DbusBus::Session => zbus::Connection::session().await.context("Failed to connect to the DBus session bus")?,
+        DbusBus::System => zbus::Connection::system().await.context("Failed to connect to the DBus system bus")?,
+    };
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    let mut owner_changes = dbus.receive_name_owner_changed().await?;
+    let mut has_owner = dbus.name_has_owner(var.service.as_str().try_into()?).await?;
+    loop {
+        if !has_owner {
+            log::info!("Waiting for DBus service {} to appear for variable {}", var.service, var.name);
+        }
+        while !has_owner {
+            has_owner = next_owner_state(&mut owner_changes, &var.service).await?;
+        }
+        tokio::select! {
+            result = watch_dbus_member(&connection, var, evt_send, failed) => return result,
+            result = next_owner_state(&mut owner_changes, &var.service) => has_owner = result?,
+        }
+    }
+}
+
+/// Wait for the owner of the given service to change, returning whether the service is now on the bus.
+async fn next_owner_state(owner_changes: &mut zbus::fdo::NameOwnerChangedStream<'_>, service: &str) -> Result<bool> {
+    while let Some(signal) = owner_changes.next().await {
+        let args = signal.args()?;
+        if args.name().as_str() == service {
+            return Ok(args.new_owner().is_some());
+        }
+    }
+    bail!("Stopped receiving name owner changes from DBus")
+}
+
+/// Subscribe to the property or signal of a DBus variable, and send every new value to the app.
+async fn watch_dbus_member(
+    connection: &zbus::Connection,
+    var: &DbusScriptVar,
+    evt_send: &UnboundedSender<DaemonCommand>,
+    failed: &mut bool,
+) -> Result<()> {
+    let proxy = zbus::Proxy::new(connection, var.service.as_str(), var.path.as_str(), var.interface.as_str()).await?;
+    match &var.member {
+        DbusMember::Property(property) => {
+            let mut changes = proxy.receive_property_changed::<OwnedValue>(property).await;
+            let value: OwnedValue = proxy
+                .get_property(property)
+                .await
+                .with_context(|| format!("Failed to read property {} of {}", property, var.service))?;
+            send_dbus_value(&var.name, evt_send, failed, dbus_value_to_dynval(&value))?;
+            while let Some(change) = changes.next().await {
+                send_dbus_value(&var.name, evt_send, failed, dbus_value_to_dynval(&change.get().await?))?;
+            }
+        }
+        DbusMember::Signal(signal) => {
+            let mut signals = proxy.receive_signal(signal.as_str()).await?;
+            while let Some(message) = signals.next().await {
+                let mut fields = message.body::<Structure>().map(|body| body.into_fields()).unwrap_or_default();
+                let value = match fields.len() {
+                    0 => DynVal::from_string(String::new()),
+                    1 => dbus_value_to_dynval(&fields.remove(0)),
+                    _ => {
+                        DynVal::from_string(serde_json::Value::Array(fields.iter().map(dbus_value_to_json).collect()).to_string())
+                    }
+                };
+                send_dbus_value(&var.name, evt_send, failed, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn send_dbus_value(name: &VarName, evt_send: &UnboundedSender<DaemonCommand>, failed: &mut bool, value: DynVal) -> Result<()> {
+    if std::mem::take(failed) {
+        evt_send.send(DaemonCommand::SetScriptVarError { name: name.clone(), error: None })?;
+    }
+    evt_send.send(DaemonCommand::UpdateVars(vec![(name.clone(), value)]))?;
+    Ok(())
+}
+
+/// Convert a DBus value into a value for eww. Strings are used as-is, all other values are turned into JSON.
+fn dbus_value_to_dynval(value: &Value) -> DynVal {
+    match value {
+        Value::Str(x) => DynVal::from_string(x.to_string()),
+        Value::Value(x) => dbus_value_to_dynval(x),
+        other => DynVal::from_string(dbus_value_to_json(other).to_string()),
+    }
+}
+
+fn dbus_value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::U8(x) => (*x).into(),
+        Value::Bool(x) => (*x).into(),
+        Value::I16(x) => (*x).into(),
+        Value::U16(x) => (*x).into(),
+        Value::I32(x) => (*x).into(),
+        Value::U32(x) => (*x).into(),
+        Value::I64(x) => (*x).into(),
+        Value::U64(x) => (*x).into(),
+        Value::F64(x) => (*x).into(),
+        Value::Str(x) => x.as_str().into(),
+        Value::Signature(x) => x.as_str().into(),
+        Value::ObjectPath(x) => x.as_str().into(),
+        Value::Value(x) => dbus_value_to_json(x),
+        Value::Array(x) => x.get().iter().map(dbus_value_to_json).collect(),
+        Value::Structure(x) => x.fields().iter().map(dbus_value_to_json).collect(),
+        Value::Dict(_) => match HashMap::<String, OwnedValue>::try_from(value.clone()) {
+            Ok(entries) => entries.into_iter().map(|(key, value)| (key, dbus_value_to_json(&value))).collect(),
+            // Dictionaries with non-string keys can't be represented in JSON
+            Err(_) => serde_json::Value::Null,
+        },
+        #[allow(unreachable_patterns)]
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// Spawn a command in its own process group, such that it can be terminated together with all the processes it started.
+/// If a working directory is given, the command is run in that directory instead of the configuration directory.
+fn spawn_in_process_group(command: &str, cwd: Option<&str>) -> std::io::Result<(tokio::process::Child, ProcessGroupGuard)> {
+    let mut process = tokio::process::Command::new("sh");
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    let child = unsafe {
+        process
+            .args(&["-c", command])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .stdin(std::process::Stdio::null())
+            .pre_exec(|| {
+                let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+                Ok(())
+            })
+            .spawn()?
+    };
+    let process_group = ProcessGroupGuard(child.id().map(|id| Pid::from_raw(id as i32)));
+    Ok((child, process_group))
+}
+
+/// Run a command in its own process group and get its output, like [`script_var::run_command`].
+/// If the returned future is dropped before the command has finished, the process group is terminated.
+async fn run_in_process_group(command: &str, cwd: Option<&str>) -> Result<String> {
+    let (child, mut process_group) = spawn_in_process_group(command, cwd)?;
+    let output = child.wait_with_output().await?;
+    // Processes that the finished command started in the background are intentionally left running.
+    process_group.release();
+    if !output.status.success() {
+        bail!("Failed with output:\n{}", String::from_utf8(output.stderr)?);
+    }
+    Ok(String::from_utf8(output.stdout)?.trim_matches('\n').to_string())
+}
+
+/// Process group of a spawned script, which gets terminated when this is dropped.
+/// This ensures that no processes are left behind when the task running the script is torn down without
+/// terminating the script itself, i.e. because the runtime shuts down.
+struct ProcessGroupGuard(Option<Pid>);
+
+impl ProcessGroupGuard {
+    /// Stop tracking the process group, such that it is not terminated when this is dropped.
+    fn release(&mut self) {
+        self.0 = None;
+    }
+
+    fn terminate(&mut self) {
+        if let Some(pgid) = self.0.take() {
+            let _ = signal::killpg(pgid, signal::SIGTERM);
+        }
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+async fn terminate_handle(mut child: tokio::process::Child, mut process_group: ProcessGroupGuard) {
+    if child.id().is_some() {
+        process_group.terminate();
         tokio::select! {
             _ = child.wait() => {},
             _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
@@ -273,3 +685,100 @@ async fn terminate_handle(mut child: tokio::process::Child) {
         let _ = child.kill().await;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use eww_shared_util::Span;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_dbus_value_to_dynval() {
+        assert_eq!(dbus_value_to_dynval(&Value::from("hi")), DynVal::from("hi"));
+        assert_eq!(dbus_value_to_dynval(&Value::from(12u32)), DynVal::from("12"));
+        assert_eq!(dbus_value_to_dynval(&Value::Value(Box::new(Value::from(true)))), DynVal::from("true"));
+        assert_eq!(dbus_value_to_dynval(&Value::from(vec!["a", "b"])), DynVal::from(r#"["a","b"]"#));
+    }
+
+    #[test]
+    fn test_listen_var_lines_keeps_max_lines() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (evt_send, mut evt_recv) = tokio::sync::mpsc::unbounded_channel();
+            let mut handler = ListenVarHandler::new(evt_send).unwrap();
+            handler
+                .start(
+                    ListenScriptVar {
+                        name: VarName::from("recent"),
+                        command: ListenSource::Shell(r"printf 'a\nb\nc\n'".to_string()),
+                        initial_value: DynVal::from(""),
+                        cwd: None,
+                        format: VarFormat::Lines,
+                        max_lines: Some(2),
+                        command_span: Span::DUMMY,
+                        name_span: Span::DUMMY,
+                    },
+                    r"printf 'a\nb\nc\n'".to_string(),
+                    None,
+                )
+                .await;
+            let mut values = Vec::new();
+            while values.len() < 3 {
+                let command = tokio::time::timeout(std::time::Duration::from_secs(5), evt_recv.recv()).await.unwrap().unwrap();
+                if let DaemonCommand::UpdateVars(updates) = command {
+                    values.extend(updates.into_iter().map(|(_, value)| value));
+                }
+            }
+            assert_eq!(values, vec![DynVal::from(r#"["a"]"#), DynVal::from(r#"["a","b"]"#), DynVal::from(r#"["b","c"]"#)]);
+        });
+    }
+
+    #[test]
+    fn test_dropping_process_group_terminates_script() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (mut child, process_group) = spawn_in_process_group("sleep 30", None).unwrap();
+            drop(process_group);
+            let status = tokio::time::timeout(std::time::Duration::from_secs(5), child.wait()).await.unwrap().unwrap();
+            assert_eq!(status.signal(), Some(signal::SIGTERM as i32));
+        });
+    }
+
+    /// Check whether the process with the given pid is still running, treating zombie processes as not running.
+    fn is_running(pid: i32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat.rsplit(')').next().unwrap_or_default().trim_start().starts_with('Z'),
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn test_cancelled_poll_command_is_terminated() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let pid_file = std::env::temp_dir().join(format!("eww_poll_test_{}", std::process::id()));
+            let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+            let timeout = std::time::Duration::from_millis(500);
+            assert!(tokio::time::timeout(timeout, run_in_process_group(&command, None)).await.is_err());
+
+            let pid: i32 = std::fs::read_to_string(&pid_file).unwrap().trim().parse().unwrap();
+            std::fs::remove_file(&pid_file).unwrap();
+            for _ in 0..50 {
+                if !is_running(pid) {
+                    return;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            panic!("The background process of the cancelled command is still running");
+        });
+    }
+
+    #[test]
+    fn test_poll_command_output() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            assert_eq!(run_in_process_group("echo hi", None).await.unwrap(), "hi");
+            assert!(run_in_process_group("exit 1", None).await.is_err());
+        });
+    }
+}
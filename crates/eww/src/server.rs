@@ -1,7 +1,7 @@
 use crate::{
     app::{self, DaemonCommand},
     config, daemon_response, error_handling_ctx, ipc_server, script_var_handler,
-    state::scope_graph::ScopeGraph,
+    state::{scope_graph::ScopeGraph, temporary_overrides::TemporaryOverrides},
     util, EwwPaths,
 };
 use anyhow::{Context, Result};
@@ -22,6 +22,7 @@ pub fn initialize_server(paths: EwwPaths, action: Option<DaemonCommand>, should_
     std::env::set_current_dir(&paths.get_config_dir())
         .with_context(|| format!("Failed to change working directory to {}", paths.get_config_dir().display()))?;
 
+    log::info!("Using configuration directory {}", paths.get_config_dir().display());
     log::info!("Loading paths: {}", &paths);
 
     let read_config = config::read_from_eww_paths(&paths);
@@ -61,7 +62,7 @@ pub fn initialize_server(paths: EwwPaths, action: Option<DaemonCommand>, should_
     gtk::init()?;
 
     log::debug!("Initializing script var handler");
-    let script_var_handler = script_var_handler::init(ui_send.clone());
+    let script_var_handler = script_var_handler::init(ui_send.clone(), paths.get_poll_cache_file().to_path_buf());
 
     let (scope_graph_evt_send, mut scope_graph_evt_recv) = tokio::sync::mpsc::unbounded_channel();
 
@@ -73,6 +74,9 @@ pub fn initialize_server(paths: EwwPaths, action: Option<DaemonCommand>, should_
         eww_config,
         open_windows: HashMap::new(),
         failed_windows: HashSet::new(),
+        temporary_var_overrides: TemporaryOverrides::default(),
+        script_var_errors: HashMap::new(),
+        paused_vars: HashSet::new(),
         css_provider: gtk::CssProvider::new(),
         script_var_handler,
         app_evt_send: ui_send.clone(),
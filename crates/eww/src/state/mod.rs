@@ -1,6 +1,7 @@
 mod one_to_n_elements_map;
 pub mod scope;
 pub mod scope_graph;
+pub mod temporary_overrides;
 
 #[cfg(test)]
 mod test;
@@ -132,6 +132,17 @@ impl ScopeGraph {
         self.graph.scope_at(index)
     }
 
+    /// Get all scopes that where created within the given scope, directly or indirectly.
+    /// For the scope of a window, these are the scopes of all the widgets within that window.
+    pub fn descendants_of(&self, index: ScopeIndex) -> Vec<ScopeIndex> {
+        let mut result = Vec::new();
+        for (descendant, _) in self.graph.descendant_edges_of(index) {
+            result.push(descendant);
+            result.extend(self.descendants_of(descendant));
+        }
+        result
+    }
+
     /// Evaluate a [SimplExpr] in a given scope. This will return `Err` if any referenced variables
     /// are not available in the scope. If evaluation fails for other reasons (bad types, etc)
     /// this will print a warning and return an empty string instead.
@@ -0,0 +1,65 @@
+use eww_shared_util::VarName;
+use simplexpr::dynval::DynVal;
+use std::collections::HashMap;
+
+/// Variables that have been updated temporarily via `eww update --for`, along with the values they should be reverted to.
+#[derive(Debug, Default)]
+pub struct TemporaryOverrides {
+    /// The value each variable should be reverted to, and the generation of its latest pending revert.
+    overrides: HashMap<VarName, (DynVal, u64)>,
+    /// Generations are unique across all overrides, such that reverts of a forgotten override never match a later one.
+    next_generation: u64,
+}
+
+impl TemporaryOverrides {
+    /// Register a temporary override of the variable with the given current value, returning the generation of its revert.
+    /// If the variable is already overridden, the value from before the first override is kept.
+    pub fn insert(&mut self, name: VarName, current_value: DynVal) -> u64 {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        self.overrides.entry(name).and_modify(|(_, latest)| *latest = generation).or_insert((current_value, generation));
+        generation
+    }
+
+    /// Take the value to revert the variable to, if the revert with the given generation is still the latest one.
+    pub fn take_revert(&mut self, name: &VarName, generation: u64) -> Option<DynVal> {
+        match self.overrides.get(name) {
+            Some((_, latest)) if *latest == generation => self.overrides.remove(name).map(|(value, _)| value),
+            _ => None,
+        }
+    }
+
+    /// Forget the override of a variable that has been set to a new value, such that it is not reverted anymore.
+    pub fn remove(&mut self, name: &VarName) {
+        self.overrides.remove(name);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_revert_to_value_before_first_override() {
+        let mut overrides = TemporaryOverrides::default();
+        let first = overrides.insert(VarName::from("foo"), DynVal::from("a"));
+        let second = overrides.insert(VarName::from("foo"), DynVal::from("b"));
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), first), None);
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), second), Some(DynVal::from("a")));
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), second), None);
+    }
+
+    #[test]
+    fn test_update_during_override_is_not_reverted() {
+        let mut overrides = TemporaryOverrides::default();
+        let pending = overrides.insert(VarName::from("foo"), DynVal::from("a"));
+        // the variable is set by `eww update` or its script while the override is pending
+        overrides.remove(&VarName::from("foo"));
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), pending), None);
+
+        // a later override is not reverted by the timer of the forgotten one
+        let later = overrides.insert(VarName::from("foo"), DynVal::from("c"));
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), pending), None);
+        assert_eq!(overrides.take_revert(&VarName::from("foo"), later), Some(DynVal::from("c")));
+    }
+}
@@ -1,7 +1,10 @@
 use super::scope::Listener;
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use eww_shared_util::{Span, VarName};
@@ -74,6 +77,29 @@ pub fn test_delete_scope() {
     assert!(scope_graph.scope_at(widget_bar_scope).is_none());
 }
 
+#[test]
+pub fn test_descendants_of() {
+    let (send, _recv) = tokio::sync::mpsc::unbounded_channel();
+
+    let mut scope_graph = ScopeGraph::from_global_vars(HashMap::new(), send);
+
+    let window_scope = scope_graph
+        .register_new_scope("window".to_string(), Some(scope_graph.root_index), scope_graph.root_index, HashMap::new())
+        .unwrap();
+    let widget_foo_scope =
+        scope_graph.register_new_scope("foo".to_string(), Some(scope_graph.root_index), window_scope, HashMap::new()).unwrap();
+    let widget_bar_scope = scope_graph
+        .register_new_scope("bar".to_string(), Some(scope_graph.root_index), widget_foo_scope, HashMap::new())
+        .unwrap();
+    let other_window_scope = scope_graph
+        .register_new_scope("other".to_string(), Some(scope_graph.root_index), scope_graph.root_index, HashMap::new())
+        .unwrap();
+
+    let descendants: HashSet<_> = scope_graph.descendants_of(window_scope).into_iter().collect();
+    assert_eq!(descendants, HashSet::from_iter(vec![widget_foo_scope, widget_bar_scope]));
+    assert!(scope_graph.descendants_of(other_window_scope).is_empty());
+}
+
 #[test]
 fn test_state_updates() {
     let globals = hashmap! {
@@ -147,11 +147,14 @@ impl<I: Iterator<Item = f32>> IterAverage for I {
 }
 
 /// Replace all env-var references of the format `"something ${foo}"` in a string
-/// by the actual env-variables. If the env-var isn't found, will replace the
-/// reference with an empty string.
+/// by the actual env-variables. A default can be given as `${foo:-default}`, which is used if the env-var isn't found.
+/// If the env-var isn't found and no default is given, will replace the reference with an empty string.
 pub fn replace_env_var_references(input: String) -> String {
-    regex!(r"\$\{([^\s]*)\}")
-        .replace_all(&input, |var_name: &regex::Captures| std::env::var(var_name.get(1).unwrap().as_str()).unwrap_or_default())
+    regex!(r"\$\{([^\s}:]*)(?::-([^}]*))?\}")
+        .replace_all(&input, |captures: &regex::Captures| {
+            std::env::var(captures.get(1).unwrap().as_str())
+                .unwrap_or_else(|_| captures.get(2).map(|default| default.as_str().to_string()).unwrap_or_default())
+        })
         .into_owned()
 }
 
@@ -190,6 +193,25 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_replace_env_var_references_set_var_with_default() {
+        std::env::set_var("EWW_TEST_SET_VAR", "#ff0000");
+        assert_eq!(replace_env_var_references(String::from("$accent: ${EWW_TEST_SET_VAR:-#8888ff};")), "$accent: #ff0000;");
+    }
+
+    #[test]
+    fn test_replace_env_var_references_unset_var_with_default() {
+        std::env::remove_var("EWW_TEST_UNSET_VAR");
+        assert_eq!(replace_env_var_references(String::from("$accent: ${EWW_TEST_UNSET_VAR:-#8888ff};")), "$accent: #8888ff;");
+        assert_eq!(replace_env_var_references(String::from("$accent: ${EWW_TEST_UNSET_VAR:-};")), "$accent: ;");
+    }
+
+    #[test]
+    fn test_replace_env_var_references_unset_var_without_default() {
+        std::env::remove_var("EWW_TEST_UNSET_VAR_NO_DEFAULT");
+        assert_eq!(replace_env_var_references(String::from("$accent: ${EWW_TEST_UNSET_VAR_NO_DEFAULT};")), "$accent: ;");
+    }
+
     #[test]
     fn test_unindent() {
         let indented = "
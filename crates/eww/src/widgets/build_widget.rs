@@ -1,9 +1,9 @@
 use anyhow::{Context, Result};
 use codespan_reporting::diagnostic::Severity;
-use eww_shared_util::{AttrName, Spanned};
+use eww_shared_util::{AttrName, Spanned, VarName};
 use gdk::prelude::Cast;
 use gtk::{
-    prelude::{BoxExt, ContainerExt, WidgetExt, WidgetExtManual},
+    prelude::{BoxExt, ContainerExt, CssProviderExt, ExpanderExt, ObjectExt, StyleContextExt, WidgetExt, WidgetExtManual},
     Orientation,
 };
 use itertools::Itertools;
@@ -25,10 +25,14 @@ use crate::{
         scope::Listener,
         scope_graph::{ScopeGraph, ScopeGraphEvent, ScopeIndex},
     },
+    util,
     widgets::widget_definitions,
 };
 
-use super::widget_definitions::{resolve_orientable_attrs, resolve_range_attrs, resolve_widget_attrs};
+use super::widget_definitions::{resolve_orientable_attrs, resolve_range_attrs, resolve_widget_attrs, update_classes};
+
+/// Attributes that, when given to a custom widget that doesn't declare them as arguments, are applied to its root widget.
+const INHERITED_ATTRS: &[&str] = &["class", "style"];
 
 pub struct BuilderArgs<'a> {
     pub calling_scope: ScopeIndex,
@@ -37,6 +41,9 @@ pub struct BuilderArgs<'a> {
     pub unhandled_attrs: Vec<AttrName>,
     pub widget_defs: Rc<HashMap<String, WidgetDefinition>>,
     pub custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
+    /// Set by builders that wrap the widget they build in other widgets, such as a [`gtk::ScrolledWindow`].
+    /// This widget then receives the children as well as the `class` and `style` attributes instead of the outer widget.
+    pub wrapped_widget: Option<gtk::Widget>,
 }
 
 // TODO in case of custom widgets, we should add a validation step where
@@ -88,6 +95,12 @@ fn build_basic_gtk_widget(
             })
             .collect::<Result<HashMap<_, _>>>()?;
 
+        let inherited_attrs = INHERITED_ATTRS
+            .iter()
+            .filter(|attr| !custom_widget.expected_args.iter().any(|spec| spec.name.0 == **attr))
+            .filter_map(|attr| widget_use.attrs.ast_optional::<SimplExpr>(attr).transpose().map(|expr| Ok((*attr, expr?))))
+            .collect::<Result<Vec<_>>>()?;
+
         let root_index = graph.root_index;
         let new_scope_index =
             graph.register_new_scope(widget_use.name, Some(root_index), calling_scope, widget_use_attributes)?;
@@ -100,6 +113,10 @@ fn build_basic_gtk_widget(
             Some(Rc::new(CustomWidgetInvocation { scope: calling_scope, children: widget_use.children })),
         )?;
 
+        for (attr, expr) in inherited_attrs {
+            apply_inherited_attr(graph, calling_scope, &gtk_widget, attr, expr)?;
+        }
+
         let scope_graph_sender = graph.event_sender.clone();
 
         gtk_widget.connect_destroy(move |_| {
@@ -111,6 +128,44 @@ fn build_basic_gtk_widget(
     }
 }
 
+/// Apply a `class` or `style` given to a custom widget invocation to the root widget of that custom widget.
+/// These are applied on top of the classes and style that the root widget defines itself,
+/// with the style of the invocation taking precedence.
+fn apply_inherited_attr(
+    graph: &mut ScopeGraph,
+    calling_scope: ScopeIndex,
+    gtk_widget: &gtk::Widget,
+    attr: &str,
+    expr: SimplExpr,
+) -> Result<()> {
+    let set_classes = RefCell::new(Vec::new());
+    let css_provider = gtk::CssProvider::new();
+    let is_class = attr == "class";
+    graph.register_listener(
+        calling_scope,
+        Listener {
+            needed_variables: expr.collect_var_refs(),
+            f: Box::new({
+                let gtk_widget = gtk_widget.downgrade();
+                move |_, values| {
+                    let gtk_widget = match gtk_widget.upgrade() {
+                        Some(gtk_widget) => gtk_widget,
+                        None => return Ok(()),
+                    };
+                    let value = expr.eval(&values)?.as_string()?;
+                    if is_class {
+                        update_classes(&gtk_widget, &set_classes, &value);
+                    } else {
+                        css_provider.load_from_data(format!("* {{ {} }}", value).as_bytes())?;
+                        gtk_widget.style_context().add_provider(&css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1);
+                    }
+                    Ok(())
+                }
+            }),
+        },
+    )
+}
+
 /// build a [`gtk::Widget`] out of a [`WidgetUse`] that uses a
 /// **builtin widget**. User defined widgets are handled by [`widget_definitions::widget_use_to_gtk_widget`].
 ///
@@ -133,12 +188,13 @@ fn build_builtin_gtk_widget(
         widget_use,
         widget_defs,
         custom_widget_invocation,
+        wrapped_widget: None,
     };
     let gtk_widget = widget_definitions::widget_use_to_gtk_widget(&mut bargs)?;
+    let inner_widget = bargs.wrapped_widget.clone().unwrap_or_else(|| gtk_widget.clone());
 
-    if let Some(gtk_container) = gtk_widget.dynamic_cast_ref::<gtk::Container>() {
+    if let Some(gtk_container) = inner_widget.dynamic_cast_ref::<gtk::Container>() {
         validate_container_children_count(gtk_container, &bargs.widget_use)?;
-
         // Only populate children if there haven't been any children added anywhere else
         // TODO this is somewhat hacky
         if gtk_container.children().is_empty() {
@@ -174,7 +230,7 @@ fn build_builtin_gtk_widget(
 
 /// If a gtk widget can take children (→ it is a [`gtk::Container`]) we need to add the provided `widget_use_children`
 /// into that container. Those children might be uses of the special `children`-[`WidgetUse`], which will get expanded here, too.
-fn populate_widget_children(
+pub(super) fn populate_widget_children(
     tree: &mut ScopeGraph,
     widget_defs: Rc<HashMap<String, WidgetDefinition>>,
     calling_scope: ScopeIndex,
@@ -222,10 +278,28 @@ fn build_loop_special_widget(
     gtk_container: &gtk::Container,
     custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
 ) -> Result<()> {
+    // Keeping children across updates requires moving them into the order of their elements, which only boxes support.
+    let keyed_box = match (&widget_use.key_expr, gtk_container.dynamic_cast_ref::<gtk::Box>()) {
+        (Some(_), Some(gtk_box)) => Some(gtk_box.clone()),
+        (Some(_), None) => {
+            return Err(
+                DiagError::new(gen_diagnostic!("A `for` with a `:key` can only be used within a `box`", widget_use.span)).into()
+            )
+        }
+        (None, _) => None,
+    };
+    let key_var_refs = widget_use.key_expr.iter().flat_map(|key_expr| key_expr.collect_var_refs());
+    let needed_variables = widget_use
+        .elements_expr
+        .collect_var_refs()
+        .into_iter()
+        .chain(key_var_refs.filter(|var_ref| var_ref != &widget_use.element_name))
+        .unique()
+        .collect_vec();
     tree.register_listener(
         calling_scope,
         Listener {
-            needed_variables: widget_use.elements_expr.collect_var_refs(),
+            needed_variables,
             f: Box::new({
                 let custom_widget_invocation = custom_widget_invocation.clone();
                 let widget_defs = widget_defs.clone();
@@ -234,6 +308,16 @@ fn build_loop_special_widget(
                 let element_name = widget_use.element_name.clone();
                 let body: WidgetUse = widget_use.body.as_ref().clone();
                 let created_children = Rc::new(RefCell::new(Vec::<gtk::Widget>::new()));
+                let keyed_loop = keyed_box.map(|gtk_box| {
+                    RefCell::new(KeyedLoop {
+                        widget_use: widget_use.clone(),
+                        calling_scope,
+                        widget_defs: widget_defs.clone(),
+                        gtk_box,
+                        custom_widget_invocation: custom_widget_invocation.clone(),
+                        children: Vec::new(),
+                    })
+                });
                 let gtk_container = gtk_container.clone();
                 move |tree, values| {
                     let elements_value = elements_expr
@@ -244,6 +328,9 @@ fn build_loop_special_widget(
                         .into_iter()
                         .map(DynVal::from)
                         .collect_vec();
+                    if let Some(keyed_loop) = &keyed_loop {
+                        return keyed_loop.borrow_mut().update(tree, &values, elements_value);
+                    }
                     let mut created_children = created_children.borrow_mut();
                     for old_child in created_children.drain(..) {
                         unsafe { old_child.destroy() };
@@ -270,6 +357,193 @@ fn build_loop_special_widget(
     )
 }
 
+/// A child of a `for` with a `:key`, which is kept across updates for as long as an element with the same key exists.
+struct KeyedLoopChild {
+    key: String,
+    scope: ScopeIndex,
+    widget: gtk::Widget,
+}
+
+/// The state of a `for` with a `:key`, which reuses the children of elements whose key is unchanged across updates.
+struct KeyedLoop {
+    widget_use: LoopWidgetUse,
+    calling_scope: ScopeIndex,
+    widget_defs: Rc<HashMap<String, WidgetDefinition>>,
+    gtk_box: gtk::Box,
+    custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
+    children: Vec<KeyedLoopChild>,
+}
+
+impl KeyedLoop {
+    /// Update the children to match the given elements.
+    /// Children of keys that are still present are reused, and get their element updated, such that they keep their widget state.
+    /// Children of removed keys are destroyed, new keys get a new child, and all children are then ordered like their elements.
+    fn update(&mut self, tree: &mut ScopeGraph, values: &HashMap<VarName, DynVal>, elements: Vec<DynVal>) -> Result<()> {
+        let element_name = &self.widget_use.element_name;
+        let key_expr = self.widget_use.key_expr.as_ref().context("Not a keyed `for`")?;
+        let mut keyed_elements: Vec<(String, DynVal)> = Vec::new();
+        for element in elements {
+            let mut key_values = values.clone();
+            key_values.insert(element_name.clone(), element.clone());
+            let key = key_expr.eval(&key_values)?.0;
+            if keyed_elements.iter().any(|(other_key, _)| other_key == &key) {
+                anyhow::bail!("The key `{}` is used by multiple elements of a `for`", key);
+            }
+            keyed_elements.push((key, element));
+        }
+
+        let old_keys = self.children.iter().map(|child| child.key.clone()).collect_vec();
+        let new_keys = keyed_elements.iter().map(|(key, _)| key.clone()).collect_vec();
+        let (removed_keys, _) = util::list_difference(&old_keys, &new_keys);
+        let mut old_children: HashMap<String, KeyedLoopChild> =
+            self.children.drain(..).map(|child| (child.key.clone(), child)).collect();
+        for key in removed_keys {
+            if let Some(child) = old_children.remove(key) {
+                unsafe { child.widget.destroy() };
+            }
+        }
+
+        let elements_expr_span = self.widget_use.elements_expr_span;
+        for (key, element) in keyed_elements {
+            let child = match old_children.remove(&key) {
+                Some(child) => {
+                    if tree.lookup_variable_in_scope(child.scope, element_name).map(|value| &value.0) != Some(&element.0) {
+                        tree.update_value(child.scope, element_name, DynVal(element.0, elements_expr_span))?;
+                    }
+                    child
+                }
+                None => {
+                    let scope = tree.register_new_scope(
+                        format!("for {} = {}", element_name.0, element),
+                        Some(self.calling_scope),
+                        self.calling_scope,
+                        hashmap! {
+                            element_name.clone().into() => SimplExpr::Literal(DynVal(element.0, elements_expr_span))
+                        },
+                    )?;
+                    let widget = build_gtk_widget(
+                        tree,
+                        self.widget_defs.clone(),
+                        scope,
+                        self.widget_use.body.as_ref().clone(),
+                        self.custom_widget_invocation.clone(),
+                    )?;
+                    let scope_graph_sender = tree.event_sender.clone();
+                    widget.connect_destroy(move |_| {
+                        let _ = scope_graph_sender.send(ScopeGraphEvent::RemoveScope(scope));
+                    });
+                    self.gtk_box.add(&widget);
+                    KeyedLoopChild { key, scope, widget }
+                }
+            };
+            // Moving the existing widgets keeps their state, unlike rebuilding them in the new order.
+            self.gtk_box.reorder_child(&child.widget, -1);
+            self.children.push(child);
+        }
+        Ok(())
+    }
+}
+
+/// Maximum nesting depth of the nodes given to a `tree` widget, to protect against unbounded recursion.
+const MAX_TREE_DEPTH: usize = 32;
+
+/// The template used to render each node of a `tree` widget.
+pub(super) struct TreeTemplate {
+    /// The widget that is built for every node
+    pub body: WidgetUse,
+    /// The name of the variable the current node is bound to in the scope of [`Self::body`]
+    pub var_name: VarName,
+    /// Whether nodes with children should initially be expanded
+    pub expanded: bool,
+    /// The indentation of child nodes, in pixels
+    pub indent: i32,
+    /// Whether the node with a given `id` is expanded, overriding [`Self::expanded`]
+    pub expansion_states: Rc<RefCell<HashMap<String, bool>>>,
+    pub custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
+}
+
+/// Build the widgets for the given nodes of a `tree` widget, and add them to the `gtk_container`.
+/// Every node gets it's own scope, in which the node is available as [`TreeTemplate::var_name`].
+/// Nodes that contain a non-empty `children` array are rendered as a [`gtk::Expander`] containing their children.
+pub(super) fn build_tree_nodes(
+    tree: &mut ScopeGraph,
+    widget_defs: Rc<HashMap<String, WidgetDefinition>>,
+    calling_scope: ScopeIndex,
+    template: &TreeTemplate,
+    nodes: &[serde_json::Value],
+    gtk_container: &gtk::Container,
+    depth: usize,
+) -> Result<()> {
+    if depth > MAX_TREE_DEPTH {
+        anyhow::bail!("Tree nodes are nested deeper than the maximum depth of {}", MAX_TREE_DEPTH);
+    }
+    for node in nodes {
+        let node_value = DynVal::from(node);
+        let scope = tree.register_new_scope(
+            format!("tree node {} = {}", template.var_name.0, node_value),
+            Some(calling_scope),
+            calling_scope,
+            hashmap! {
+                template.var_name.clone().into() => SimplExpr::synth_literal(node_value)
+            },
+        )?;
+        let node_widget =
+            build_gtk_widget(tree, widget_defs.clone(), scope, template.body.clone(), template.custom_widget_invocation.clone())?;
+
+        let children = node.get("children").and_then(|x| x.as_array()).filter(|x| !x.is_empty());
+        let gtk_widget: gtk::Widget = if let Some(children) = children {
+            let children_box = gtk::Box::new(Orientation::Vertical, 0);
+            children_box.set_margin_start(template.indent);
+            build_tree_nodes(tree, widget_defs.clone(), scope, template, children, children_box.upcast_ref(), depth + 1)?;
+
+            let node_id = node.get("id").map(|id| id.to_string());
+            let expanded = node_id.as_ref().and_then(|id| template.expansion_states.borrow().get(id).copied());
+
+            let expander = gtk::Expander::new(None);
+            expander.set_label_widget(Some(&node_widget));
+            expander.set_expanded(expanded.unwrap_or(template.expanded));
+            expander.add(&children_box);
+            if let Some(node_id) = node_id {
+                let expansion_states = template.expansion_states.clone();
+                expander.connect_expanded_notify(move |expander| {
+                    expansion_states.borrow_mut().insert(node_id.clone(), expander.is_expanded());
+                });
+            }
+            expander.upcast()
+        } else {
+            node_widget
+        };
+
+        let scope_graph_sender = tree.event_sender.clone();
+        gtk_widget.connect_destroy(move |_| {
+            let _ = scope_graph_sender.send(ScopeGraphEvent::RemoveScope(scope));
+        });
+        gtk_container.add(&gtk_widget);
+        gtk_widget.show_all();
+    }
+    Ok(())
+}
+
+/// Build the body of the branch of a `switch` widget in a new scope, which is removed once the returned widget is destroyed.
+pub(super) fn build_switch_branch(
+    tree: &mut ScopeGraph,
+    widget_defs: Rc<HashMap<String, WidgetDefinition>>,
+    calling_scope: ScopeIndex,
+    branch_index: usize,
+    body: WidgetUse,
+    custom_widget_invocation: Option<Rc<CustomWidgetInvocation>>,
+) -> Result<gtk::Widget> {
+    let scope =
+        tree.register_new_scope(format!("switch branch {}", branch_index), Some(calling_scope), calling_scope, HashMap::new())?;
+    let branch_widget = build_gtk_widget(tree, widget_defs, scope, body, custom_widget_invocation)?;
+
+    let scope_graph_sender = tree.event_sender.clone();
+    branch_widget.connect_destroy(move |_| {
+        let _ = scope_graph_sender.send(ScopeGraphEvent::RemoveScope(scope));
+    });
+    Ok(branch_widget)
+}
+
 /// Handle an invocation of the special `children` [`WidgetUse`].
 /// This widget expands to multiple other widgets, thus we require the `gtk_container` we should expand the widgets into.
 /// The `custom_widget_invocation` will be used here to evaluate the provided children in their
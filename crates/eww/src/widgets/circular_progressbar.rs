@@ -5,6 +5,9 @@ use std::cell::RefCell;
 
 use crate::error_handling_ctx;
 
+/// Distance between the labels of the ticks and the ring, in pixels.
+const TICK_LABEL_SPACING: f64 = 2.0;
+
 wrapper! {
     pub struct CircProg(ObjectSubclass<CircProgPriv>)
     @extends gtk::Bin, gtk::Container, gtk::Widget;
@@ -15,6 +18,8 @@ pub struct CircProgPriv {
     value: RefCell<f64>,
     thickness: RefCell<f64>,
     clockwise: RefCell<bool>,
+    ticks: RefCell<u32>,
+    tick_labels: RefCell<Vec<String>>,
     content: RefCell<Option<gtk::Widget>>,
 }
 
@@ -26,6 +31,8 @@ impl Default for CircProgPriv {
             value: RefCell::new(0.0),
             thickness: RefCell::new(1.0),
             clockwise: RefCell::new(true),
+            ticks: RefCell::new(0),
+            tick_labels: RefCell::new(Vec::new()),
             content: RefCell::new(None),
         }
     }
@@ -56,6 +63,7 @@ impl ObjectImpl for CircProgPriv {
                     glib::ParamFlags::READWRITE,
                 ),
                 glib::ParamSpec::new_boolean("clockwise", "Clockwise", "Clockwise", true, glib::ParamFlags::READWRITE),
+                glib::ParamSpec::new_uint("ticks", "Ticks", "Number of ticks", 0u32, u32::MAX, 0u32, glib::ParamFlags::READWRITE),
             ]
         });
 
@@ -77,6 +85,10 @@ impl ObjectImpl for CircProgPriv {
             "clockwise" => {
                 self.clockwise.replace(value.get().unwrap());
             }
+            "ticks" => {
+                self.ticks.replace(value.get().unwrap());
+                obj.queue_draw();
+            }
             x => panic!("Tried to set inexistant property of CircProg: {}", x,),
         }
     }
@@ -87,6 +99,7 @@ impl ObjectImpl for CircProgPriv {
             "start-at" => self.start_at.borrow().to_value(),
             "thickness" => self.thickness.borrow().to_value(),
             "clockwise" => self.clockwise.borrow().to_value(),
+            "ticks" => self.ticks.borrow().to_value(),
             x => panic!("Tried to access inexistant property of CircProg: {}", x,),
         }
     }
@@ -108,6 +121,12 @@ impl CircProg {
     pub fn new() -> Self {
         glib::Object::new::<Self>(&[]).expect("Failed to create CircularProgress Widget")
     }
+
+    /// Set the labels drawn next to the ticks, starting at the first tick.
+    pub fn set_tick_labels(&self, labels: Vec<String>) {
+        CircProgPriv::from_instance(self).tick_labels.replace(labels);
+        self.queue_draw();
+    }
 }
 
 impl ContainerImpl for CircProgPriv {
@@ -120,6 +139,13 @@ impl ContainerImpl for CircProgPriv {
         self.parent_add(container, widget);
         self.content.replace(Some(widget.clone()));
     }
+
+    fn remove(&self, container: &Self::Type, widget: &gtk::Widget) {
+        if self.content.borrow().as_ref() == Some(widget) {
+            self.content.replace(None);
+        }
+        self.parent_remove(container, widget);
+    }
 }
 
 fn calc_widget_lowest_preferred_dimension(widget: &gtk::Widget) -> (i32, i32) {
@@ -168,6 +194,21 @@ impl WidgetImpl for CircProgPriv {
         self.preferred_height(widget)
     }
 
+    // The child is placed inside of the ring, so we don't give it the space taken up by the margins.
+    fn size_allocate(&self, widget: &Self::Type, allocation: &gtk::Allocation) {
+        widget.set_allocation(allocation);
+        if let Some(child) = &*self.content.borrow() {
+            let margin = widget.style_context().margin(gtk::StateFlags::NORMAL);
+            let child_allocation = gtk::Allocation {
+                x: allocation.x + margin.left as i32,
+                y: allocation.y + margin.top as i32,
+                width: i32::max(allocation.width - margin.left as i32 - margin.right as i32, 0),
+                height: i32::max(allocation.height - margin.top as i32 - margin.bottom as i32, 0),
+            };
+            child.size_allocate(&child_allocation);
+        }
+    }
+
     fn draw(&self, widget: &Self::Type, cr: &cairo::Context) -> Inhibit {
         let res: Result<()> = try {
             let value = *self.value.borrow();
@@ -218,6 +259,38 @@ impl WidgetImpl for CircProgPriv {
             cr.fill()?;
             cr.restore()?;
 
+            // Ticks, drawn across the ring
+            let ticks = *self.ticks.borrow();
+            if ticks > 0 {
+                cr.save()?;
+                cr.set_source_rgba(fg_color.red, fg_color.green, fg_color.blue, fg_color.alpha);
+                cr.set_line_width(1.0);
+                for tick in 0..ticks {
+                    let angle = perc_to_rad(start_at + tick_percentage(tick, ticks, clockwise));
+                    cr.move_to(center.0 + inner_ring * angle.cos(), center.1 + inner_ring * angle.sin());
+                    cr.line_to(center.0 + outer_ring * angle.cos(), center.1 + outer_ring * angle.sin());
+                }
+                cr.stroke()?;
+
+                // Labels are placed inside of the ring, moved inwards by their extents such that they never overlap it
+                let font = styles.font(gtk::StateFlags::NORMAL);
+                if let Some(family) = font.family() {
+                    cr.select_font_face(&family, cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+                }
+                cr.set_font_size(font.size() as f64 / gtk::pango::SCALE as f64);
+                for (tick, label) in (0..ticks).zip(self.tick_labels.borrow().iter()) {
+                    let angle = perc_to_rad(start_at + tick_percentage(tick, ticks, clockwise));
+                    let extents = cr.text_extents(label)?;
+                    let distance = inner_ring
+                        - TICK_LABEL_SPACING
+                        - (extents.width / 2.0 * angle.cos().abs() + extents.height / 2.0 * angle.sin().abs());
+                    let (x, y) = (center.0 + distance * angle.cos(), center.1 + distance * angle.sin());
+                    cr.move_to(x - extents.width / 2.0 - extents.x_bearing, y - extents.height / 2.0 - extents.y_bearing);
+                    cr.show_text(label)?;
+                }
+                cr.restore()?;
+            }
+
             // Draw the children widget, clipping it to the inside
             if let Some(child) = &*self.content.borrow() {
                 cr.save()?;
@@ -243,6 +316,16 @@ impl WidgetImpl for CircProgPriv {
     }
 }
 
+/// Position of the given tick along the ring, in percent, relative to the start of the ring.
+fn tick_percentage(tick: u32, ticks: u32, clockwise: bool) -> f64 {
+    let percentage = tick as f64 / ticks as f64 * 100.0;
+    if clockwise {
+        percentage
+    } else {
+        -percentage
+    }
+}
+
 fn perc_to_rad(n: f64) -> f64 {
     (n / 100f64) * 2f64 * std::f64::consts::PI
 }
@@ -20,11 +20,15 @@ pub struct GraphPriv {
     line_style: RefCell<String>,
     min: RefCell<f64>,
     max: RefCell<f64>,
+    // The value the area below or above the line is filled towards
+    baseline: RefCell<f64>,
     dynamic: RefCell<bool>,
     time_range: RefCell<u64>,
     history: RefCell<VecDeque<(std::time::Instant, f64)>>,
     extra_point: RefCell<Option<(std::time::Instant, f64)>>,
     last_updated_at: RefCell<std::time::Instant>,
+    // When set, these values are plotted directly instead of the history
+    values: RefCell<Option<Vec<f64>>>,
 }
 
 impl Default for GraphPriv {
@@ -35,11 +39,13 @@ impl Default for GraphPriv {
             line_style: RefCell::new("miter".to_string()),
             min: RefCell::new(0.0),
             max: RefCell::new(100.0),
+            baseline: RefCell::new(0.0),
             dynamic: RefCell::new(true),
             time_range: RefCell::new(10),
             history: RefCell::new(VecDeque::new()),
             extra_point: RefCell::new(None),
             last_updated_at: RefCell::new(std::time::Instant::now()),
+            values: RefCell::new(None),
         }
     }
 }
@@ -68,7 +74,7 @@ impl ObjectImpl for GraphPriv {
         use once_cell::sync::Lazy;
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
             vec![
-                glib::ParamSpec::new_double("value", "Value", "The value", 0f64, 100f64, 0f64, glib::ParamFlags::READWRITE),
+                glib::ParamSpec::new_double("value", "Value", "The value", f64::MIN, f64::MAX, 0f64, glib::ParamFlags::READWRITE),
                 glib::ParamSpec::new_double(
                     "thickness",
                     "Thickness",
@@ -82,7 +88,7 @@ impl ObjectImpl for GraphPriv {
                     "max",
                     "Maximum Value",
                     "The Maximum Value",
-                    0f64,
+                    f64::MIN,
                     f64::MAX,
                     100f64,
                     glib::ParamFlags::READWRITE,
@@ -91,7 +97,16 @@ impl ObjectImpl for GraphPriv {
                     "min",
                     "Minumum Value",
                     "The Minimum Value",
+                    f64::MIN,
+                    f64::MAX,
                     0f64,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpec::new_double(
+                    "baseline",
+                    "Baseline",
+                    "The value the graph is filled towards",
+                    f64::MIN,
                     f64::MAX,
                     0f64,
                     glib::ParamFlags::READWRITE,
@@ -136,6 +151,9 @@ impl ObjectImpl for GraphPriv {
             "min" => {
                 self.min.replace(value.get().unwrap());
             }
+            "baseline" => {
+                self.baseline.replace(value.get().unwrap());
+            }
             "dynamic" => {
                 self.dynamic.replace(value.get().unwrap());
             }
@@ -155,6 +173,7 @@ impl ObjectImpl for GraphPriv {
             "thickness" => self.thickness.borrow().to_value(),
             "max" => self.max.borrow().to_value(),
             "min" => self.min.borrow().to_value(),
+            "baseline" => self.baseline.borrow().to_value(),
             "dynamic" => self.dynamic.borrow().to_value(),
             "time-range" => self.time_range.borrow().to_value(),
             "line-style" => self.line_style.borrow().to_value(),
@@ -179,6 +198,12 @@ impl Graph {
     pub fn new() -> Self {
         glib::Object::new::<Self>(&[]).expect("Failed to create Graph Widget")
     }
+
+    /// Plot the given values, spread evenly across the width of the graph, instead of the history of `value`.
+    pub fn set_values(&self, values: Option<Vec<f64>>) {
+        GraphPriv::from_instance(self).values.replace(values);
+        self.queue_draw();
+    }
 }
 
 impl ContainerImpl for GraphPriv {
@@ -211,27 +236,25 @@ impl WidgetImpl for GraphPriv {
         let res: Result<()> = try {
             let history = &*self.history.borrow();
             let extra_point = *self.extra_point.borrow();
+            let values = &*self.values.borrow();
 
-            // Calculate the max value
+            // Calculate the range of values to show
             let (min, max) = {
-                let mut max = *self.max.borrow();
+                let max = *self.max.borrow();
                 let min = *self.min.borrow();
                 let dynamic = *self.dynamic.borrow() as bool;
                 if dynamic {
-                    // Check for points higher than max
-                    for (_, value) in history {
-                        if *value > max {
-                            max = *value;
-                        }
-                    }
-                    if let Some((_, value)) = extra_point {
-                        if value > max {
-                            max = value;
-                        }
-                    }
+                    let shown_values = history
+                        .iter()
+                        .map(|(_, value)| *value)
+                        .chain(extra_point.map(|(_, value)| value))
+                        .chain(values.iter().flatten().copied());
+                    dynamic_range(min, max, shown_values)
+                } else {
+                    (min, max)
                 }
-                (min, max)
             };
+            let baseline = *self.baseline.borrow();
 
             let styles = widget.style_context();
             let (margin_top, margin_right, margin_bottom, margin_left) = {
@@ -244,8 +267,9 @@ impl WidgetImpl for GraphPriv {
             // Calculate graph points once
             //  Separating this into another function would require pasing a
             //  GraphPriv that would hide interior mutability
-            let points = {
-                let value_range = max - min;
+            let points = if let Some(values) = values {
+                static_points(values, width, height, min, max)
+            } else {
                 let time_range = *self.time_range.borrow() as f64;
                 let last_updated_at = self.last_updated_at.borrow();
                 let mut points = history
@@ -253,8 +277,7 @@ impl WidgetImpl for GraphPriv {
                     .map(|(instant, value)| {
                         let t = last_updated_at.duration_since(*instant).as_millis() as f64;
                         let x = width * (1.0 - (t / time_range));
-                        let y = height * (1.0 - ((value - min) / value_range));
-                        (x, y)
+                        (x, value_to_y(*value, height, min, max))
                     })
                     .collect::<VecDeque<(f64, f64)>>();
 
@@ -262,8 +285,7 @@ impl WidgetImpl for GraphPriv {
                 if let Some((instant, value)) = extra_point {
                     let t = last_updated_at.duration_since(instant).as_millis() as f64;
                     let x = -width * ((t - time_range) / time_range);
-                    let y = height * (1.0 - ((value - min) / value_range));
-                    points.push_front((x, y));
+                    points.push_front((x, value_to_y(value, height, min, max)));
                 }
                 points
             };
@@ -274,16 +296,17 @@ impl WidgetImpl for GraphPriv {
             cr.rectangle(0.0, 0.0, width, height);
             cr.clip();
 
-            // Draw Background
+            // Draw Background, filling the area between the line and the baseline
             let bg_color: gdk::RGBA = styles.style_property_for_state("background-color", gtk::StateFlags::NORMAL).get()?;
             if bg_color.alpha > 0.0 {
+                let baseline_y = value_to_y(baseline, height, min, max);
                 if let Some(first_point) = points.front() {
-                    cr.line_to(first_point.0, height + margin_bottom);
+                    cr.line_to(first_point.0, baseline_y);
                 }
                 for (x, y) in points.iter() {
                     cr.line_to(*x, *y);
                 }
-                cr.line_to(width, height);
+                cr.line_to(width, baseline_y);
 
                 cr.set_source_rgba(bg_color.red, bg_color.green, bg_color.blue, bg_color.alpha);
                 cr.fill()?;
@@ -316,6 +339,31 @@ impl WidgetImpl for GraphPriv {
     }
 }
 
+/// Extend the range from `min` to `max` such that it contains all of the given values.
+fn dynamic_range(min: f64, max: f64, values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((min, max), |(min, max), value| (min.min(value), max.max(value)))
+}
+
+/// Map a value to the y coordinate it is drawn at in a graph of the given height showing the range from `min` to `max`.
+/// Values below `min`, such as a negative value with a `min` of 0, end up below the graph.
+fn value_to_y(value: f64, height: f64, min: f64, max: f64) -> f64 {
+    height * (1.0 - ((value - min) / (max - min)))
+}
+
+/// Calculate the points of a graph plotting the given values, spread evenly from the left to the right edge.
+fn static_points(values: &[f64], width: f64, height: f64, min: f64, max: f64) -> VecDeque<(f64, f64)> {
+    let y = |value: f64| value_to_y(value, height, min, max);
+    match values {
+        [] => VecDeque::new(),
+        // A single value is drawn as a flat line across the whole graph
+        [value] => VecDeque::from(vec![(0.0, y(*value)), (width, y(*value))]),
+        _ => {
+            let step = width / (values.len() - 1) as f64;
+            values.iter().enumerate().map(|(i, value)| (i as f64 * step, y(*value))).collect()
+        }
+    }
+}
+
 fn apply_line_style(style: &str, cr: &cairo::Context) -> Result<()> {
     match style {
         "miter" => {
@@ -334,3 +382,26 @@ fn apply_line_style(style: &str, cr: &cairo::Context) -> Result<()> {
     };
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_static_points() {
+        assert!(static_points(&[], 100.0, 10.0, 0.0, 100.0).is_empty());
+        assert_eq!(static_points(&[50.0], 100.0, 10.0, 0.0, 100.0), VecDeque::from(vec![(0.0, 5.0), (100.0, 5.0)]));
+        assert_eq!(
+            static_points(&[0.0, 100.0, 50.0], 100.0, 10.0, 0.0, 100.0),
+            VecDeque::from(vec![(0.0, 10.0), (50.0, 0.0), (100.0, 5.0)])
+        );
+        assert_eq!(static_points(&[-50.0, 50.0], 100.0, 10.0, -50.0, 50.0), VecDeque::from(vec![(0.0, 10.0), (100.0, 0.0)]));
+    }
+
+    #[test]
+    fn test_dynamic_range() {
+        assert_eq!(dynamic_range(0.0, 100.0, vec![10.0, 50.0].into_iter()), (0.0, 100.0));
+        assert_eq!(dynamic_range(0.0, 100.0, vec![-20.0, 150.0].into_iter()), (-20.0, 150.0));
+        assert_eq!(value_to_y(0.0, 10.0, -20.0, 20.0), 5.0);
+    }
+}
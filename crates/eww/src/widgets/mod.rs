@@ -1,4 +1,17 @@
-use std::process::Command;
+use gtk::prelude::{Cast, IsA, ObjectExt, WidgetExt};
+use nix::{
+    sys::signal,
+    unistd::{setpgid, Pid},
+};
+use std::{
+    collections::HashSet,
+    os::unix::process::CommandExt,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
 pub mod build_widget;
 pub mod circular_progressbar;
@@ -8,32 +21,131 @@ mod system_tray;
 pub mod transform;
 pub mod widget_definitions;
 
-/// Run a command that was provided as an attribute.
+/// Key of the widget data that holds the working directory of the commands run by a widget, as set by its `cwd` attribute.
+const COMMAND_CWD_KEY: &str = "eww-command-cwd";
+
+/// Set the working directory of the commands run by the given widget.
+pub(self) fn set_command_cwd(widget: &impl IsA<gtk::Widget>, cwd: String) {
+    unsafe { widget.set_data(COMMAND_CWD_KEY, cwd) }
+}
+
+/// Get the working directory of the commands run by the given widget.
+/// Without a working directory, commands run in the configuration directory.
+fn command_cwd(widget: &impl IsA<gtk::Widget>) -> Option<String> {
+    unsafe { widget.data::<String>(COMMAND_CWD_KEY).map(|cwd| cwd.as_ref().clone()) }
+}
+
+/// Key of the widget data of a window that holds the process groups of the commands run by its widgets.
+const RUNNING_COMMANDS_KEY: &str = "eww-running-commands";
+
+/// Process groups of the commands that are currently being run by the widgets of a window.
+type RunningCommands = Arc<Mutex<HashSet<Pid>>>;
+
+/// Get the process groups of the commands run by the widgets of the window the given widget is in, if it is in one.
+fn window_commands(widget: &impl IsA<gtk::Widget>) -> Option<RunningCommands> {
+    let window = widget.toplevel().filter(|toplevel| toplevel.is_toplevel())?;
+    unsafe {
+        match window.data::<RunningCommands>(RUNNING_COMMANDS_KEY) {
+            Some(running) => Some(running.as_ref().clone()),
+            None => {
+                let running = RunningCommands::default();
+                window.set_data(RUNNING_COMMANDS_KEY, running.clone());
+                Some(running)
+            }
+        }
+    }
+}
+
+/// Terminate the commands that the widgets of the given window are still running, along with all processes they started.
+pub fn kill_window_commands(window: &impl IsA<gtk::Window>) {
+    if let Some(running) = window_commands(window.upcast_ref::<gtk::Widget>()) {
+        for pgid in running.lock().unwrap().drain() {
+            let _ = signal::killpg(pgid, signal::SIGTERM);
+        }
+    }
+}
+
+/// Run a command that was provided as an attribute of the given widget, in the working directory of that widget.
 /// This command may use placeholders which will be replaced by the values of the arguments given.
 /// This can either be the placeholder `{}`, which will be replaced by the first argument,
 /// Or a placeholder like `{0}`, `{1}`, etc, which will refer to the respective argument.
-pub(self) fn run_command<T>(timeout: std::time::Duration, cmd: &str, args: &[T])
+pub(self) fn run_command<T>(widget: &impl IsA<gtk::Widget>, timeout: std::time::Duration, cmd: &str, args: &[T])
 where
     T: 'static + std::fmt::Display + Send + Sync + Clone,
 {
-    use wait_timeout::ChildExt;
+    spawn_command(timeout, replace_placeholders(cmd, args), command_cwd(widget), window_commands(widget), None);
+}
+
+/// Run a command like [`run_command`], unless a previous invocation sharing the same `running` flag is still running.
+/// In that case, the new invocation is dropped.
+pub(self) fn run_command_exclusive<T>(
+    widget: &impl IsA<gtk::Widget>,
+    timeout: std::time::Duration,
+    cmd: &str,
+    args: &[T],
+    running: &Arc<AtomicBool>,
+) where
+    T: 'static + std::fmt::Display + Send + Sync + Clone,
+{
     let cmd = replace_placeholders(cmd, args);
+    if running.swap(true, Ordering::SeqCst) {
+        log::debug!("Not running command {}, as the previous invocation is still running", cmd);
+        return;
+    }
+    spawn_command(timeout, cmd, command_cwd(widget), window_commands(widget), Some(running.clone()));
+}
+
+/// Spawn the given command in its own process group in a separate thread,
+/// killing the process group if the command does not finish within the timeout.
+/// While it is running, the process group is tracked in `window_commands`, such that it can be terminated when the window closes.
+/// If a `running` flag is given, it is reset once the command has finished.
+fn spawn_command(
+    timeout: std::time::Duration,
+    cmd: String,
+    cwd: Option<String>,
+    window_commands: Option<RunningCommands>,
+    running: Option<Arc<AtomicBool>>,
+) {
+    use wait_timeout::ChildExt;
     std::thread::spawn(move || {
         log::debug!("Running command from widget: {}", cmd);
-        let child = Command::new("/bin/sh").arg("-c").arg(&cmd).spawn();
+        let mut command = Command::new("/bin/sh");
+        command.arg("-c").arg(&cmd);
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        unsafe {
+            command.pre_exec(|| {
+                let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+                Ok(())
+            });
+        }
+        let child = command.spawn();
         match child {
-            Ok(mut child) => match child.wait_timeout(timeout) {
-                // child timed out
-                Ok(None) => {
-                    log::error!("WARNING: command {} timed out", &cmd);
-                    let _ = child.kill();
-                    let _ = child.wait();
+            Ok(mut child) => {
+                let pgid = Pid::from_raw(child.id() as i32);
+                if let Some(window_commands) = &window_commands {
+                    window_commands.lock().unwrap().insert(pgid);
                 }
-                Err(err) => log::error!("Failed to execute command {}: {}", cmd, err),
-                Ok(Some(_)) => {}
-            },
+                match child.wait_timeout(timeout) {
+                    // child timed out
+                    Ok(None) => {
+                        log::error!("WARNING: command {} timed out", &cmd);
+                        let _ = signal::killpg(pgid, signal::SIGKILL);
+                        let _ = child.wait();
+                    }
+                    Err(err) => log::error!("Failed to execute command {}: {}", cmd, err),
+                    Ok(Some(_)) => {}
+                }
+                if let Some(window_commands) = &window_commands {
+                    window_commands.lock().unwrap().remove(&pgid);
+                }
+            }
             Err(err) => log::error!("Failed to launch child process: {}", err),
         }
+        if let Some(running) = running {
+            running.store(false, Ordering::SeqCst);
+        }
     });
 }
 
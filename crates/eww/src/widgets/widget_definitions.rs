@@ -1,37 +1,45 @@
 #![allow(clippy::option_map_unit_fn)]
-use super::{build_widget::BuilderArgs, circular_progressbar::*, run_command, transform::*};
+use super::{
+    build_widget::BuilderArgs, circular_progressbar::*, run_command, run_command_exclusive, set_command_cwd, transform::*,
+};
 use crate::{
     def_widget, enum_parse,
     error::DiagError,
     error_handling_ctx,
+    state::scope::Listener,
     util::{list_difference, unindent},
-    widgets::build_widget::build_gtk_widget,
+    widgets::build_widget::{build_gtk_widget, build_switch_branch, build_tree_nodes, populate_widget_children, TreeTemplate},
 };
 use anyhow::{anyhow, Context, Result};
 use codespan_reporting::diagnostic::Severity;
-use eww_shared_util::Spanned;
+use eww_shared_util::{Spanned, VarName};
 use gdk::{ModifierType, NotifyType};
 use glib::translate::FromGlib;
 use glib::signal::SignalHandlerId;
-use gtk::{self, glib, prelude::*, DestDefaults, TargetEntry, TargetList};
+use gtk::{self, atk::prelude::*, glib, prelude::*, DestDefaults, TargetEntry, TargetList};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
+use simplexpr::SimplExpr;
 use std::hash::Hasher;
 
 use crate::widgets::system_tray::{spawn_local_handler, start_communication_thread};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     cmp::Ordering,
     collections::{HashMap, HashSet},
     rc::Rc,
+    sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
 use tokio::sync::mpsc;
 use yuck::{
-    config::validate::ValidationError,
+    config::{
+        validate::{ChildVariable, ValidationError},
+        widget_use::WidgetUse,
+    },
     error::{AstError, AstResult},
     gen_diagnostic,
-    parser::from_ast::FromAst,
+    parser::{ast::Ast, from_ast::FromAst},
 };
 
 /// Connect a gtk signal handler inside of this macro to ensure that when the same code gets run multiple times,
@@ -82,14 +90,21 @@ pub const BUILTIN_WIDGET_NAMES: &[&str] = &[
     WIDGET_NAME_COMBO_BOX_TEXT,
     WIDGET_NAME_CHECKBOX,
     WIDGET_NAME_REVEALER,
+    WIDGET_NAME_REVEAL_ON_HOVER,
     WIDGET_NAME_SCROLL,
     WIDGET_NAME_OVERLAY,
+    WIDGET_NAME_TREE,
+    WIDGET_NAME_SWITCH,
+    WIDGET_NAME_SEPARATOR,
 ];
 
+/// Variables that builtin widgets make available to their children
+pub const BUILTIN_CHILD_VARIABLES: &[ChildVariable] = &[TREE_NODE_VARIABLE];
+
 //// widget definitions
 pub(super) fn widget_use_to_gtk_widget(bargs: &mut BuilderArgs) -> Result<gtk::Widget> {
     let gtk_widget = match bargs.widget_use.name.as_str() {
-        WIDGET_NAME_BOX => build_gtk_box(bargs)?.upcast(),
+        WIDGET_NAME_BOX => build_gtk_box(bargs)?,
         WIDGET_NAME_CENTERBOX => build_center_box(bargs)?.upcast(),
         WIDGET_NAME_EVENTBOX => build_gtk_event_box(bargs)?.upcast(),
         WIDGET_NAME_CIRCULAR_PROGRESS => build_circular_progress_bar(bargs)?.upcast(),
@@ -109,9 +124,13 @@ pub(super) fn widget_use_to_gtk_widget(bargs: &mut BuilderArgs) -> Result<gtk::W
         WIDGET_NAME_COMBO_BOX_TEXT => build_gtk_combo_box_text(bargs)?.upcast(),
         WIDGET_NAME_CHECKBOX => build_gtk_checkbox(bargs)?.upcast(),
         WIDGET_NAME_REVEALER => build_gtk_revealer(bargs)?.upcast(),
+        WIDGET_NAME_REVEAL_ON_HOVER => build_reveal_on_hover(bargs)?.upcast(),
         WIDGET_NAME_SCROLL => build_gtk_scrolledwindow(bargs)?.upcast(),
         WIDGET_NAME_OVERLAY => build_gtk_overlay(bargs)?.upcast(),
         WIDGET_NAME_SYSTRAY => build_gtk_system_tray(bargs)?.upcast(),
+        WIDGET_NAME_TREE => build_tree(bargs)?.upcast(),
+        WIDGET_NAME_SWITCH => build_switch(bargs)?.upcast(),
+        WIDGET_NAME_SEPARATOR => build_gtk_separator(bargs)?.upcast(),
         _ => {
             return Err(AstError::ValidationError(ValidationError::UnknownWidget(
                 bargs.widget_use.name_span,
@@ -127,6 +146,24 @@ pub(super) fn widget_use_to_gtk_widget(bargs: &mut BuilderArgs) -> Result<gtk::W
 static DEPRECATED_ATTRS: Lazy<HashSet<&str>> =
     Lazy::new(|| ["timeout", "onscroll", "onhover", "cursor"].iter().cloned().collect());
 
+/// Replace the css classes previously added to the widget through `set_classes` by the given space-separated classes.
+/// Classes added by gtk itself or through other means, such as the `class` of a custom widget invocation, are kept.
+pub(super) fn update_classes(gtk_widget: &gtk::Widget, set_classes: &RefCell<Vec<String>>, classes: &str) {
+    let mut set_classes = set_classes.borrow_mut();
+    let new_classes = classes.split(' ').filter(|class| !class.is_empty()).collect::<Vec<_>>();
+    {
+        let old_classes = set_classes.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
+        let (missing, new) = list_difference(&old_classes, &new_classes);
+        for class in missing {
+            gtk_widget.style_context().remove_class(class);
+        }
+        for class in new {
+            gtk_widget.style_context().add_class(class);
+        }
+    }
+    *set_classes = new_classes.into_iter().map(str::to_string).collect();
+}
+
 /// attributes that apply to all widgets
 /// @widget widget
 /// @desc these properties apply to _all_ widgets, and can be used anywhere!
@@ -144,6 +181,7 @@ pub(super) fn resolve_widget_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Wi
     }
 
     let css_provider = gtk::CssProvider::new();
+    let set_classes = RefCell::new(Vec::new());
 
     let visible_result: Result<_> = try {
         let visible_expr = bargs.widget_use.attrs.attrs.get("visible").map(|x| x.value.as_simplexpr()).transpose()?;
@@ -162,21 +200,21 @@ pub(super) fn resolve_widget_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Wi
         error_handling_ctx::print_error(err);
     }
 
-    def_widget!(bargs, _g, gtk_widget, {
+    // `class`, `style` and `cwd` are applied to the widget that is wrapped by `gtk_widget`, if any,
+    // as that is the widget that gets styled and runs the commands.
+    let styled_widget = bargs.wrapped_widget.clone().unwrap_or_else(|| gtk_widget.clone());
+    def_widget!(bargs, _g, styled_widget, {
         // @prop class - css class name
-        prop(class: as_string) {
-            // TODO currently this overrides classes that gtk adds automatically, which is kinda stupid...
-            let old_classes = gtk_widget.style_context().list_classes();
-            let old_classes = old_classes.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
-            let new_classes = class.split(' ').collect::<Vec<_>>();
-            let (missing, new) = list_difference(&old_classes, &new_classes);
-            for class in missing {
-                gtk_widget.style_context().remove_class(class);
-            }
-            for class in new {
-                gtk_widget.style_context().add_class(class);
-            }
-        },
+        prop(class: as_string) { update_classes(&styled_widget, &set_classes, &class) },
+        // @prop cwd - working directory of the commands run by this widget, such as `onclick` or `onchange`. Relative paths are relative to the configuration directory, which is also the default.
+        prop(cwd: as_string) { set_command_cwd(&styled_widget, cwd) },
+    });
+
+    // the role a presentational widget is restored to once it stops being presentational
+    let original_role = gtk_widget.accessible().map(|accessible| accessible.role());
+    def_widget!(bargs, _g, gtk_widget, {
+        // @prop id - identifier of the widget, i.e. for `eww widget-geometry`. This sets the name of the widget, so it can also be targeted via `#id` in css.
+        prop(id: as_string) { gtk_widget.set_widget_name(&id) },
         // @prop valign - how to align this vertically. possible values: $alignment
         prop(valign: as_string) { gtk_widget.set_valign(parse_align(&valign)?) },
         // @prop halign - how to align this horizontally. possible values: $alignment
@@ -185,13 +223,10 @@ pub(super) fn resolve_widget_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Wi
         prop(vexpand: as_bool = false) { gtk_widget.set_vexpand(vexpand) },
         // @prop hexpand - should this widget expand horizontally. Default: false.
         prop(hexpand: as_bool = false) { gtk_widget.set_hexpand(hexpand) },
-        // @prop width - width of this element. note that this can not restrict the size if the contents stretch it
-        // @prop height - height of this element. note that this can not restrict the size if the contents stretch it
+        // @prop width - minimum width of this element, in pixels. This reserves space even if the content is smaller, but can not restrict the size if the contents stretch it. Negative values use the natural width. In contrast to the css `min-width`, which not all widgets support, this works for any widget.
+        // @prop height - minimum height of this element, in pixels. This reserves space even if the content is smaller, but can not restrict the size if the contents stretch it. Negative values use the natural height. In contrast to the css `min-height`, which not all widgets support, this works for any widget.
         prop(width: as_i32?, height: as_i32?) {
-            gtk_widget.set_size_request(
-                width.unwrap_or_else(|| gtk_widget.allocated_width()),
-                height.unwrap_or_else(|| gtk_widget.allocated_height())
-            );
+            gtk_widget.set_size_request(size_request(width), size_request(height));
         },
         // @prop active - If this widget can be interacted with
         prop(active: as_bool = true) { gtk_widget.set_sensitive(active) },
@@ -199,15 +234,36 @@ pub(super) fn resolve_widget_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Wi
         prop(tooltip: as_string) {
             gtk_widget.set_tooltip_text(Some(&tooltip));
         },
+        // @prop accessible-name - name of the widget read out by screen readers and other assistive technologies
+        prop(accessible_name: as_string) {
+            if let Some(accessible) = gtk_widget.accessible() {
+                accessible.set_name(&accessible_name);
+            }
+        },
+        // @prop accessible-description - description of the widget exposed to assistive technologies
+        prop(accessible_description: as_string) {
+            if let Some(accessible) = gtk_widget.accessible() {
+                accessible.set_description(&accessible_description);
+            }
+        },
+        // @prop accessible-presentational - mark this widget as purely decorative, such that assistive technologies skip it. Default: false.
+        prop(accessible_presentational: as_bool = false) {
+            if let (Some(accessible), Some(original_role)) = (gtk_widget.accessible(), original_role) {
+                accessible.set_role(if accessible_presentational { gtk::atk::Role::Filler } else { original_role });
+            }
+        },
         // @prop visible - visibility of the widget
         prop(visible: as_bool = true) {
             if visible { gtk_widget.show(); } else { gtk_widget.hide(); }
         },
+    });
+
+    def_widget!(bargs, _g, styled_widget, {
         // @prop style - inline css style applied to the widget
         prop(style: as_string) {
-            gtk_widget.reset_style();
+            styled_widget.reset_style();
             css_provider.load_from_data(format!("* {{ {} }}", style).as_bytes())?;
-            gtk_widget.style_context().add_provider(&css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION)
+            styled_widget.style_context().add_provider(&css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION)
         },
     });
     Ok(())
@@ -228,30 +284,71 @@ pub(super) fn resolve_range_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Ran
         gtk::Inhibit(false)
     }));
 
+    // set while the value or its bounds are being changed via properties, such that onchange only runs for changes made by the user
+    let is_applying_value = Rc::new(Cell::new(false));
+    let is_applying_bounds = is_applying_value.clone();
+    let ignore_value_change = is_applying_value.clone();
+
     def_widget!(bargs, _g, gtk_widget, {
-        // @prop value - the value
+        // min and max are handled first, such that the initial value is clamped to the correct bounds.
+        // @prop min - the minimum value
+        // @prop max - the maximum value
+        prop(min: as_f64?, max: as_f64?) {
+            // changing the bounds may clamp the current value
+            is_applying_bounds.set(true);
+            if let Some(min) = min {
+                gtk_widget.adjustment().set_lower(min);
+            }
+            if let Some(max) = max {
+                gtk_widget.adjustment().set_upper(max);
+            }
+            is_applying_bounds.set(false);
+        },
+        // @prop value - the value. Values outside of the range given by `min` and `max` are clamped into that range.
         prop(value: as_f64) {
             if !*is_being_dragged.borrow() {
-                gtk_widget.set_value(value)
+                let adjustment = gtk_widget.adjustment();
+                is_applying_value.set(true);
+                gtk_widget.set_value(clamp_to_range(value, adjustment.lower(), adjustment.upper()));
+                is_applying_value.set(false);
             }
         },
-        // @prop min - the minimum value
-        prop(min: as_f64) { gtk_widget.adjustment().set_lower(min)},
-        // @prop max - the maximum value
-        prop(max: as_f64) { gtk_widget.adjustment().set_upper(max)},
+        // @prop step - the amount the value changes by when using the arrow keys
+        prop(step: as_f64) { gtk_widget.adjustment().set_step_increment(step) },
+        // @prop page-step - the amount the value changes by when using the page up and page down keys
+        prop(page_step: as_f64) { gtk_widget.adjustment().set_page_increment(page_step) },
         // @prop timeout - timeout of the command
-        // @prop onchange - command executed once the value is changes. The placeholder `{}`, used in the command will be replaced by the new value.
+        // @prop onchange - command executed once the value is changes, either by dragging or via the keyboard. Changes caused by updating `value` do not run this command. The placeholder `{}`, used in the command will be replaced by the new value.
         prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
             gtk_widget.set_sensitive(true);
+            gtk_widget.set_can_focus(true);
             gtk_widget.add_events(gdk::EventMask::PROPERTY_CHANGE_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_value_changed(move |gtk_widget| {
-                run_command(timeout, &onchange, &[gtk_widget.value()]);
-            }));
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_value_changed(glib::clone!(@strong ignore_value_change => move |gtk_widget| {
+                if !ignore_value_change.get() {
+                    run_command(gtk_widget, timeout, &onchange, &[gtk_widget.value()]);
+                }
+            })));
         }
     });
     Ok(())
 }
 
+/// Convert an optional size given by the user into a size request, where `-1` means that the natural size is used.
+fn size_request(size: Option<i32>) -> i32 {
+    size.map_or(-1, |size| size.max(-1))
+}
+
+/// Clamp the value into the range between `min` and `max`.
+/// In contrast to [`f64::clamp`], this does not panic if `min` is greater than `max`.
+fn clamp_to_range(value: f64, min: f64, max: f64) -> f64 {
+    value.max(min).min(max)
+}
+
+/// Get the fraction a progress bar with the given value (between 0-100) should be filled to.
+fn progress_fraction(value: f64) -> f64 {
+    clamp_to_range(value / 100f64, 0f64, 1f64)
+}
+
 /// @widget !orientable
 pub(super) fn resolve_orientable_attrs(bargs: &mut BuilderArgs, gtk_widget: &gtk::Range) -> Result<()> {
     def_widget!(bargs, _g, gtk_widget, {
@@ -280,7 +377,7 @@ fn build_gtk_combo_box_text(bargs: &mut BuilderArgs) -> Result<gtk::ComboBoxText
         // @prop onchange - runs the code when a item was selected, replacing {} with the item as a string
         prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
             connect_signal_handler!(gtk_widget, gtk_widget.connect_changed(move |gtk_widget| {
-                run_command(timeout, &onchange, &[gtk_widget.active_text().unwrap_or_else(|| "".into())]);
+                run_command(gtk_widget, timeout, &onchange, &[gtk_widget.active_text().unwrap_or_else(|| "".into())]);
             }));
         },
     });
@@ -306,17 +403,169 @@ const WIDGET_NAME_REVEALER: &str = "revealer";
 /// @desc A widget that can reveal a child with an animation.
 fn build_gtk_revealer(bargs: &mut BuilderArgs) -> Result<gtk::Revealer> {
     let gtk_widget = gtk::Revealer::new();
+
+    // Offset the content gets drawn at while a custom slide animation is running.
+    // Translating the context before the default draw handler runs moves the content within the revealers clip.
+    let slide_offset = Rc::new(RefCell::new((0f64, 0f64)));
+    gtk_widget.connect_draw(glib::clone!(@strong slide_offset => move |_, cr| {
+        let (x, y) = *slide_offset.borrow();
+        cr.translate(x, y);
+        gtk::Inhibit(false)
+    }));
+    let animation_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+
     def_widget!(bargs, _g, gtk_widget, {
         // @prop transition - the name of the transition. Possible values: $transition
-        prop(transition: as_string = "crossfade") { gtk_widget.set_transition_type(parse_transition(&transition)?); },
         // @prop reveal - sets if the child is revealed or not
-        prop(reveal: as_bool) { gtk_widget.set_reveal_child(reveal); },
         // @prop duration - the duration of the reveal transition
-        prop(duration: as_duration = Duration::from_millis(500)) { gtk_widget.set_transition_duration(duration.as_millis() as u32); },
+        // @prop overshoot - strength of the bounce at the end of slide transitions, i.e. `1.7` overshoots by roughly 10%. When not given, GTKs native transition is used.
+        prop(transition: as_string = "crossfade", reveal: as_bool, duration: as_duration = Duration::from_millis(500), overshoot: as_f64 = 0) {
+            let transition = parse_transition(&transition)?;
+            if let Some(source) = animation_source.borrow_mut().take() {
+                source.remove();
+            }
+            *slide_offset.borrow_mut() = (0f64, 0f64);
+
+            let slide_direction = slide_transition_direction(transition).filter(|_| overshoot > 0f64 && gtk_widget.is_mapped());
+            if let Some((direction_x, direction_y)) = slide_direction {
+                let (_, natural_size) = gtk_widget.child().map(|child| child.preferred_size()).unwrap_or_default();
+                let (width, height) = (natural_size.width as f64, natural_size.height as f64);
+                gtk_widget.set_transition_type(gtk::RevealerTransitionType::None);
+                if reveal {
+                    gtk_widget.set_reveal_child(true);
+                }
+                let start = std::time::Instant::now();
+                let source = glib::timeout_add_local(Duration::from_millis(16), glib::clone!(@strong gtk_widget, @strong slide_offset, @strong animation_source => move || {
+                    let t = (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1f64);
+                    // distance of the content from its resting position, relative to the size of the content
+                    let distance = if reveal { 1f64 - ease_out_back(t, overshoot) } else { t * t };
+                    *slide_offset.borrow_mut() = (direction_x * distance * width, direction_y * distance * height);
+                    gtk_widget.queue_draw();
+                    if t < 1f64 {
+                        return glib::Continue(true);
+                    }
+                    if !reveal {
+                        gtk_widget.set_reveal_child(false);
+                        *slide_offset.borrow_mut() = (0f64, 0f64);
+                    }
+                    animation_source.borrow_mut().take();
+                    glib::Continue(false)
+                }));
+                *animation_source.borrow_mut() = Some(source);
+            } else {
+                gtk_widget.set_transition_type(transition);
+                gtk_widget.set_transition_duration(duration.as_millis() as u32);
+                gtk_widget.set_reveal_child(reveal);
+            }
+        },
     });
     Ok(gtk_widget)
 }
 
+const WIDGET_NAME_REVEAL_ON_HOVER: &str = "reveal-on-hover";
+/// @widget reveal-on-hover
+/// @desc A container with exactly two children, a trigger and some content. The content is revealed while the widget is hovered.
+fn build_reveal_on_hover(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
+    let gtk_widget = gtk::EventBox::new();
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    let revealer = gtk::Revealer::new();
+    gtk_widget.add(&container);
+
+    if bargs.widget_use.children.len() != 2 {
+        return Err(DiagError::new(gen_diagnostic!(
+            "reveal-on-hover must contain exactly 2 elements, a trigger and the content",
+            bargs.widget_use.children_span()
+        ))
+        .into());
+    }
+    // the children end up in the inner container rather than the event box, so we add them ourselves.
+    let mut children = std::mem::take(&mut bargs.widget_use.children).into_iter().map(|child| {
+        build_gtk_widget(
+            bargs.scope_graph,
+            bargs.widget_defs.clone(),
+            bargs.calling_scope,
+            child,
+            bargs.custom_widget_invocation.clone(),
+        )
+    });
+    // we know that we have exactly two children here, so we can unwrap here.
+    let (trigger, content) = children.next_tuple().unwrap();
+    let (trigger, content) = (trigger?, content?);
+    container.pack_start(&trigger, false, false, 0);
+    revealer.add(&content);
+    container.pack_start(&revealer, true, true, 0);
+    container.show_all();
+
+    let hover_delay = Rc::new(Cell::new(Duration::ZERO));
+    let pending_reveal: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    gtk_widget.connect_enter_notify_event(
+        glib::clone!(@strong revealer, @strong hover_delay, @strong pending_reveal => move |_, evt| {
+            if evt.detail() != NotifyType::Inferior {
+                schedule_reveal(&revealer, true, hover_delay.get(), &pending_reveal);
+            }
+            gtk::Inhibit(false)
+        }),
+    );
+    gtk_widget.connect_leave_notify_event(
+        glib::clone!(@strong revealer, @strong hover_delay, @strong pending_reveal => move |_, evt| {
+            if evt.detail() != NotifyType::Inferior {
+                schedule_reveal(&revealer, false, hover_delay.get(), &pending_reveal);
+            }
+            gtk::Inhibit(false)
+        }),
+    );
+
+    def_widget!(bargs, _g, gtk_widget, {
+        // @prop transition - the name of the transition. Possible values: $transition
+        // @prop duration - the duration of the reveal transition
+        prop(transition: as_string = "slidedown", duration: as_duration = Duration::from_millis(500)) {
+            revealer.set_transition_type(parse_transition(&transition)?);
+            revealer.set_transition_duration(duration.as_millis() as u32);
+        },
+        // @prop delay - how long the widget has to be hovered before the content is revealed, and how long it has to be left before the content is hidden again
+        prop(delay: as_duration = Duration::ZERO) { hover_delay.set(delay) },
+        // @prop orientation - whether the content is revealed below or next to the trigger. possible values: $orientation
+        prop(orientation: as_string = "vertical") { container.set_orientation(parse_orientation(&orientation)?) },
+    });
+    Ok(gtk_widget)
+}
+
+/// Reveal or hide the child of the revealer once the delay has passed,
+/// cancelling any previously scheduled change that has not happened yet.
+fn schedule_reveal(revealer: &gtk::Revealer, reveal: bool, delay: Duration, pending: &Rc<RefCell<Option<glib::SourceId>>>) {
+    if let Some(source) = pending.borrow_mut().take() {
+        source.remove();
+    }
+    if delay.is_zero() {
+        revealer.set_reveal_child(reveal);
+        return;
+    }
+    let source = glib::timeout_add_local_once(
+        delay,
+        glib::clone!(@weak revealer, @strong pending => move || {
+            pending.borrow_mut().take();
+            revealer.set_reveal_child(reveal);
+        }),
+    );
+    *pending.borrow_mut() = Some(source);
+}
+
+/// Direction the content of a revealer is hidden in for the given slide transition, or `None` if it is not a slide transition.
+fn slide_transition_direction(transition: gtk::RevealerTransitionType) -> Option<(f64, f64)> {
+    match transition {
+        gtk::RevealerTransitionType::SlideRight => Some((-1f64, 0f64)),
+        gtk::RevealerTransitionType::SlideLeft => Some((1f64, 0f64)),
+        gtk::RevealerTransitionType::SlideDown => Some((0f64, -1f64)),
+        gtk::RevealerTransitionType::SlideUp => Some((0f64, 1f64)),
+        _ => None,
+    }
+}
+
+/// Easing curve that overshoots its target before settling. `overshoot` controls the strength of the overshoot.
+fn ease_out_back(t: f64, overshoot: f64) -> f64 {
+    1f64 + (overshoot + 1f64) * (t - 1f64).powi(3) + overshoot * (t - 1f64).powi(2)
+}
+
 const WIDGET_NAME_CHECKBOX: &str = "checkbox";
 /// @widget a checkbox
 /// @desc A checkbox that can trigger events on checked / unchecked.
@@ -328,7 +577,7 @@ fn build_gtk_checkbox(bargs: &mut BuilderArgs) -> Result<gtk::CheckButton> {
         // @prop onunchecked - similar to onchecked but when the widget is unchecked
         prop(timeout: as_duration = Duration::from_millis(200), onchecked: as_string = "", onunchecked: as_string = "") {
             connect_signal_handler!(gtk_widget, gtk_widget.connect_toggled(move |gtk_widget| {
-                run_command(timeout, if gtk_widget.is_active() { &onchecked } else { &onunchecked }, &[] as &[&str]);
+                run_command(gtk_widget, timeout, if gtk_widget.is_active() { &onchecked } else { &onunchecked }, &[] as &[&str]);
             }));
        }
     });
@@ -349,7 +598,7 @@ fn build_gtk_color_button(bargs: &mut BuilderArgs) -> Result<gtk::ColorButton> {
         // @prop timeout - timeout of the command
         prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
             connect_signal_handler!(gtk_widget, gtk_widget.connect_color_set(move |gtk_widget| {
-                run_command(timeout, &onchange, &[gtk_widget.rgba()]);
+                run_command(gtk_widget, timeout, &onchange, &[gtk_widget.rgba()]);
             }));
         }
     });
@@ -369,8 +618,8 @@ fn build_gtk_color_chooser(bargs: &mut BuilderArgs) -> Result<gtk::ColorChooserW
         // @prop onchange - runs the code when the color was selected
         // @prop timeout - timeout of the command
         prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_color_activated(move |_a, color| {
-                run_command(timeout, &onchange, &[*color]);
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_color_activated(move |gtk_widget, color| {
+                run_command(gtk_widget, timeout, &onchange, &[*color]);
             }));
         }
     });
@@ -382,7 +631,16 @@ const WIDGET_NAME_SCALE: &str = "scale";
 /// @widget scale extends range, orientable
 /// @desc A slider.
 fn build_gtk_scale(bargs: &mut BuilderArgs) -> Result<gtk::Scale> {
-    let gtk_widget = gtk::Scale::new(gtk::Orientation::Horizontal, Some(&gtk::Adjustment::new(0.0, 0.0, 100.0, 1.0, 1.0, 1.0)));
+    // The page size has to be 0, as otherwise the maximum value of the scale could never be reached.
+    let gtk_widget = gtk::Scale::new(gtk::Orientation::Horizontal, Some(&gtk::Adjustment::new(0.0, 0.0, 100.0, 1.0, 10.0, 0.0)));
+
+    let segmented = Rc::new(Cell::new((0u32, 0f64)));
+    connect_segmented_draw(&gtk_widget, segmented.clone(), |gtk_widget| {
+        let adjustment = gtk_widget.adjustment();
+        let range = adjustment.upper() - adjustment.lower();
+        let fraction = if range > 0f64 { (adjustment.value() - adjustment.lower()) / range } else { 0f64 };
+        (fraction, gtk_widget.is_inverted())
+    });
 
     def_widget!(bargs, _g, gtk_widget, {
         // @prop flipped - flip the direction
@@ -398,6 +656,13 @@ fn build_gtk_scale(bargs: &mut BuilderArgs) -> Result<gtk::Scale> {
 
         // @prop draw-value - draw the value of the property
         prop(draw_value: as_bool = false) { gtk_widget.set_draw_value(draw_value) },
+
+        // @prop segments - render the scale as the given amount of discrete segments instead of a slider. Filled segments use the `color`, empty segments the `background-color` of the scale.
+        // @prop segment-gap - gap between the segments, in pixels
+        prop(segments: as_i32 = 0, segment_gap: as_f64 = 2) {
+            segmented.set((segments.max(0) as u32, segment_gap.max(0f64)));
+            gtk_widget.queue_draw();
+        },
     });
     Ok(gtk_widget)
 }
@@ -407,43 +672,147 @@ const WIDGET_NAME_PROGRESS: &str = "progress";
 /// @desc A progress bar. HINT: for the `width` property to work, you may need to set the `min-width` of `progressbar > trough` in your css.
 fn build_gtk_progress(bargs: &mut BuilderArgs) -> Result<gtk::ProgressBar> {
     let gtk_widget = gtk::ProgressBar::new();
+
+    let segmented = Rc::new(Cell::new((0u32, 0f64)));
+    connect_segmented_draw(&gtk_widget, segmented.clone(), |gtk_widget| (gtk_widget.fraction(), gtk_widget.is_inverted()));
+
     def_widget!(bargs, _g, gtk_widget, {
         // @prop flipped - flip the direction
         prop(flipped: as_bool) { gtk_widget.set_inverted(flipped) },
 
-        // @prop value - value of the progress bar (between 0-100)
-        prop(value: as_f64) { gtk_widget.set_fraction(value / 100f64) },
+        // @prop value - value of the progress bar (between 0-100). Values outside of this range are clamped.
+        prop(value: as_f64) { gtk_widget.set_fraction(progress_fraction(value)) },
 
         // @prop orientation - orientation of the progress bar. possible values: $orientation
         prop(orientation: as_string) { gtk_widget.set_orientation(parse_orientation(&orientation)?) },
+
+        // @prop segments - render the progress bar as the given amount of discrete segments. Filled segments use the `color`, empty segments the `background-color` of the progress bar.
+        // @prop segment-gap - gap between the segments, in pixels
+        prop(segments: as_i32 = 0, segment_gap: as_f64 = 2) {
+            segmented.set((segments.max(0) as u32, segment_gap.max(0f64)));
+            gtk_widget.queue_draw();
+        },
     });
 
     Ok(gtk_widget)
 }
 
+/// Render the widget as discrete segments instead of its usual appearance, while `segmented` contains a non-zero
+/// amount of segments together with the gap between them. `fill` returns the filled fraction of the widget and whether it is inverted.
+fn connect_segmented_draw<W: IsA<gtk::Widget> + IsA<gtk::Orientable>>(
+    gtk_widget: &W,
+    segmented: Rc<Cell<(u32, f64)>>,
+    fill: impl Fn(&W) -> (f64, bool) + 'static,
+) {
+    gtk_widget.connect_draw(move |gtk_widget, cr| {
+        let (segments, gap) = segmented.get();
+        if segments == 0 {
+            return gtk::Inhibit(false);
+        }
+        let (fraction, inverted) = fill(gtk_widget);
+        let result = draw_segments(gtk_widget.upcast_ref(), gtk_widget.orientation(), cr, fraction, inverted, segments, gap);
+        if let Err(err) = result {
+            error_handling_ctx::print_error(err);
+        }
+        gtk::Inhibit(true)
+    });
+}
+
+/// Get the amount of filled segments of a segmented widget that is filled to the given fraction.
+fn filled_segments(fraction: f64, segments: u32) -> u32 {
+    (clamp_to_range(fraction, 0f64, 1f64) * segments as f64).round() as u32
+}
+
+fn draw_segments(
+    gtk_widget: &gtk::Widget,
+    orientation: gtk::Orientation,
+    cr: &cairo::Context,
+    fraction: f64,
+    inverted: bool,
+    segments: u32,
+    gap: f64,
+) -> Result<()> {
+    let styles = gtk_widget.style_context();
+    let fg_color: gdk::RGBA = styles.color(gtk::StateFlags::NORMAL);
+    let bg_color: gdk::RGBA = styles.style_property_for_state("background-color", gtk::StateFlags::NORMAL).get()?;
+
+    let (width, height) = (gtk_widget.allocated_width() as f64, gtk_widget.allocated_height() as f64);
+    let horizontal = orientation == gtk::Orientation::Horizontal;
+    let length = if horizontal { width } else { height };
+    let segment_length = ((length - gap * (segments - 1) as f64) / segments as f64).max(0f64);
+    let filled = filled_segments(fraction, segments);
+
+    for i in 0..segments {
+        let position = if inverted { segments - 1 - i } else { i };
+        let offset = position as f64 * (segment_length + gap);
+        if horizontal {
+            cr.rectangle(offset, 0f64, segment_length, height);
+        } else {
+            cr.rectangle(0f64, offset, width, segment_length);
+        }
+        let color = if i < filled { fg_color } else { bg_color };
+        cr.set_source_rgba(color.red, color.green, color.blue, color.alpha);
+        cr.fill()?;
+    }
+    Ok(())
+}
+
 const WIDGET_NAME_INPUT: &str = "input";
 /// @widget input
 /// @desc An input field. For this to be useful, set `focusable="true"` on the window.
 fn build_gtk_input(bargs: &mut BuilderArgs) -> Result<gtk::Entry> {
     let gtk_widget = gtk::Entry::new();
+
+    // set while the text is being changed via the value property, such that onchange only runs for changes made by the user
+    let is_applying_value = Rc::new(Cell::new(false));
+    let ignore_text_change = is_applying_value.clone();
+
     def_widget!(bargs, _g, gtk_widget, {
         // @prop value - the content of the text field
         prop(value: as_string) {
+            is_applying_value.set(true);
             gtk_widget.set_text(&value);
+            is_applying_value.set(false);
+        },
+
+        // @prop placeholder - text shown while the text field is empty and unfocused
+        prop(placeholder: as_string) { gtk_widget.set_placeholder_text(Some(&placeholder)) },
+
+        // @prop primary-icon - name of an icon to show at the start of the text field, i.e. "edit-find-symbolic"
+        prop(primary_icon: as_string) {
+            let primary_icon = Some(primary_icon.as_str()).filter(|icon| !icon.is_empty());
+            gtk_widget.set_icon_from_icon_name(gtk::EntryIconPosition::Primary, primary_icon)
+        },
+
+        // @prop secondary-icon - name of an icon to show at the end of the text field, i.e. "edit-clear-symbolic"
+        prop(secondary_icon: as_string) {
+            let secondary_icon = Some(secondary_icon.as_str()).filter(|icon| !icon.is_empty());
+            gtk_widget.set_icon_from_icon_name(gtk::EntryIconPosition::Secondary, secondary_icon)
         },
 
-        // @prop onchange - Command to run when the text changes. The placeholder `{}` will be replaced by the value
+        // @prop oniconpress - Command to run when one of the icons is clicked. The placeholder `{}` will be replaced by the position of the icon, `primary` or `secondary`.
         // @prop timeout - timeout of the command
-        prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_changed(move |gtk_widget| {
-                run_command(timeout, &onchange, &[gtk_widget.text().to_string()]);
+        prop(timeout: as_duration = Duration::from_millis(200), oniconpress: as_string) {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_icon_press(move |gtk_widget, icon_position, _| {
+                let icon_position = if icon_position == gtk::EntryIconPosition::Primary { "primary" } else { "secondary" };
+                run_command(gtk_widget, timeout, &oniconpress, &[icon_position]);
             }));
         },
+
+        // @prop onchange - Command to run when the text is changed by the user. The placeholder `{}` will be replaced by the value
+        // @prop timeout - timeout of the command
+        prop(timeout: as_duration = Duration::from_millis(200), onchange: as_string) {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_changed(glib::clone!(@strong ignore_text_change => move |gtk_widget| {
+                if !ignore_text_change.get() {
+                    run_command(gtk_widget, timeout, &onchange, &[gtk_widget.text().to_string()]);
+                }
+            })));
+        },
         // @prop onaccept - Command to run when the user hits return in the input field. The placeholder `{}` will be replaced by the value
         // @prop timeout - timeout of the command
         prop(timeout: as_duration = Duration::from_millis(200), onaccept: as_string) {
             connect_signal_handler!(gtk_widget, gtk_widget.connect_activate(move |gtk_widget| {
-                run_command(timeout, &onaccept, &[gtk_widget.text().to_string()]);
+                run_command(gtk_widget, timeout, &onaccept, &[gtk_widget.text().to_string()]);
             }));
         }
     });
@@ -460,20 +829,29 @@ fn build_gtk_button(bargs: &mut BuilderArgs) -> Result<gtk::Button> {
         prop(
             // @prop timeout - timeout of the command
             timeout: as_duration = Duration::from_millis(200),
-            // @prop onclick - a command that get's run when the button is clicked
+            // @prop onclick - a command that get's run when the button is clicked. The `{fraction_x}` and `{fraction_y}` placeholders are replaced by the position of the click, as a fraction of the widget's width and height.
             onclick: as_string = "",
             // @prop onmiddleclick - a command that get's run when the button is middleclicked
             onmiddleclick: as_string = "",
             // @prop onrightclick - a command that get's run when the button is rightclicked
-            onrightclick: as_string = ""
+            onrightclick: as_string = "",
+            // @prop no-concurrent - if true, clicks are ignored while the command of a previous click is still running
+            no_concurrent: as_bool = false
         ) {
             gtk_widget.add_events(gdk::EventMask::BUTTON_PRESS_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_button_press_event(move |_, evt| {
-                match evt.button() {
-                    1 => run_command(timeout, &onclick, &[] as &[&str]),
-                    2 => run_command(timeout, &onmiddleclick, &[] as &[&str]),
-                    3 => run_command(timeout, &onrightclick, &[] as &[&str]),
-                    _ => {},
+            let running = Arc::new(AtomicBool::new(false));
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_button_press_event(move |gtk_widget, evt| {
+                let cmd = match evt.button() {
+                    1 => &onclick,
+                    2 => &onmiddleclick,
+                    3 => &onrightclick,
+                    _ => return gtk::Inhibit(false),
+                };
+                let cmd = replace_click_position_placeholders(cmd, gtk_widget, evt);
+                if no_concurrent {
+                    run_command_exclusive(gtk_widget, timeout, &cmd, &[] as &[&str], &running);
+                } else {
+                    run_command(gtk_widget, timeout, &cmd, &[] as &[&str]);
                 }
                 gtk::Inhibit(false)
             }));
@@ -483,6 +861,15 @@ fn build_gtk_button(bargs: &mut BuilderArgs) -> Result<gtk::Button> {
     Ok(gtk_widget)
 }
 
+/// Replace the `{fraction_x}` and `{fraction_y}` placeholders of a click handler with the position of the click,
+/// given as a fraction of the width and height of the clicked widget.
+fn replace_click_position_placeholders(cmd: &str, gtk_widget: &impl IsA<gtk::Widget>, evt: &gdk::EventButton) -> String {
+    let (x, y) = evt.position();
+    let fraction = |pos: f64, size: i32| if size > 0 { (pos / size as f64).clamp(0.0, 1.0) } else { 0.0 };
+    cmd.replace("{fraction_x}", &fraction(x, gtk_widget.allocated_width()).to_string())
+        .replace("{fraction_y}", &fraction(y, gtk_widget.allocated_height()).to_string())
+}
+
 const WIDGET_NAME_IMAGE: &str = "image";
 /// @widget image
 /// @desc A widget displaying an image
@@ -508,17 +895,346 @@ fn build_gtk_image(bargs: &mut BuilderArgs) -> Result<gtk::Image> {
 const WIDGET_NAME_BOX: &str = "box";
 /// @widget box
 /// @desc the main layout container
-fn build_gtk_box(bargs: &mut BuilderArgs) -> Result<gtk::Box> {
+fn build_gtk_box(bargs: &mut BuilderArgs) -> Result<gtk::Widget> {
     let gtk_widget = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+
+    // @prop overflow - how to handle children exceeding `max-children`. possible values: $overflow.
+    // This can not reference any variables.
+    let overflow = bargs.widget_use.attrs.primitive_optional::<String, _>("overflow")?;
+    bargs.unhandled_attrs.retain(|a| a.0 != "overflow");
+    let overflow = overflow.map(|x| parse_box_overflow(&x)).transpose()?;
+    let limits_children = overflow.is_some() || bargs.widget_use.attrs.attrs.contains_key("max-children");
+    let overflow = overflow.unwrap_or(BoxOverflow::Hide);
+
+    // @prop reorderable - allow the user to reorder the children of this box via drag and drop.
+    // This can not reference any variables.
+    let reorderable = bargs.widget_use.attrs.primitive_optional::<bool, _>("reorderable")?.unwrap_or(false);
+    bargs.unhandled_attrs.retain(|a| a.0 != "reorderable");
+
+    // @prop divider - insert a separator between every two children of this box, which can be styled using `separator.divider`.
+    // Use this together with `:space-evenly false`, as the separators otherwise take up as much space as the children.
+    // This can not reference any variables, and can not be combined with `max-children` or `reorderable`.
+    let divider = bargs.widget_use.attrs.primitive_optional::<bool, _>("divider")?.unwrap_or(false);
+    bargs.unhandled_attrs.retain(|a| a.0 != "divider");
+    if divider && (limits_children || reorderable) {
+        return Err(DiagError::new(gen_diagnostic!(
+            "`divider` can not be combined with `max-children` or `reorderable`",
+            bargs.widget_use.span
+        ))
+        .into());
+    }
+    if divider {
+        BoxDividers::setup(&gtk_widget);
+    }
+
+    let children_limit: Rc<RefCell<Option<usize>>> = Rc::new(RefCell::new(None));
+    let mut indicator = None;
+    if limits_children && overflow != BoxOverflow::Scroll {
+        // The indicator is added before the box is made reorderable, such that it is not reported as one of the children.
+        let label = gtk::Label::new(None);
+        label.style_context().add_class("overflow-indicator");
+        label.set_no_show_all(true);
+        gtk_widget.pack_end(&label, false, false, 0);
+        gtk_widget.connect_add(glib::clone!(@strong children_limit, @weak label => move |gtk_widget, _| {
+            limit_box_children(gtk_widget, &label, overflow, *children_limit.borrow());
+        }));
+        gtk_widget.connect_remove(glib::clone!(@strong children_limit, @weak label => move |gtk_widget, _| {
+            limit_box_children(gtk_widget, &label, overflow, *children_limit.borrow());
+        }));
+        indicator = Some(label);
+    }
+
+    let mut outer_widget: gtk::Widget = gtk_widget.clone().upcast();
+
+    let reorder = if reorderable {
+        let event_box = gtk::EventBox::new();
+        event_box.add(&gtk_widget);
+        outer_widget = event_box.clone().upcast();
+        Some(BoxReorder::setup(&gtk_widget, event_box))
+    } else {
+        None
+    };
+
+    if limits_children && overflow == BoxOverflow::Scroll {
+        let scroll = gtk::ScrolledWindow::new::<gtk::Adjustment, gtk::Adjustment>(None, None);
+        scroll.set_propagate_natural_width(true);
+        scroll.set_propagate_natural_height(true);
+        scroll.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Never);
+        scroll.add(&outer_widget);
+        gtk_widget.connect_orientation_notify(glib::clone!(@weak scroll => move |gtk_widget| {
+            match gtk_widget.orientation() {
+                gtk::Orientation::Vertical => scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic),
+                _ => scroll.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Never),
+            }
+        }));
+        gtk_widget.connect_size_allocate(glib::clone!(@weak scroll, @strong children_limit => move |gtk_widget, _| {
+            update_box_scroll_extent(&scroll, gtk_widget, *children_limit.borrow());
+        }));
+        outer_widget = scroll.upcast();
+    }
+
+    if reorderable || (limits_children && overflow == BoxOverflow::Scroll) {
+        bargs.wrapped_widget = Some(gtk_widget.clone().upcast());
+    }
+
+    // The box already contains the indicator, so we need to add the children ourselves.
+    if indicator.is_some() {
+        populate_widget_children(
+            bargs.scope_graph,
+            bargs.widget_defs.clone(),
+            bargs.calling_scope,
+            gtk_widget.upcast_ref(),
+            std::mem::take(&mut bargs.widget_use.children),
+            bargs.custom_widget_invocation.clone(),
+        )?;
+    }
+
+    let clip_radius = Rc::new(Cell::new(None));
+    if bargs.widget_use.attrs.attrs.contains_key("clip") {
+        connect_rounded_clip(gtk_widget.upcast_ref(), clip_radius.clone());
+    }
+
     def_widget!(bargs, _g, gtk_widget, {
+        // @prop clip - clip the children of the box to its bounds, with corners rounded by `radius`. This is more expensive to draw than a plain box.
+        // @prop radius - radius of the corners when clipping the children, in pixels
+        prop(clip: as_bool = false, radius: as_f64 = 0) {
+            clip_radius.set(clip.then(|| radius.max(0f64)));
+            gtk_widget.queue_draw();
+        },
         // @prop spacing - spacing between elements
         prop(spacing: as_i32 = 0) { gtk_widget.set_spacing(spacing) },
         // @prop orientation - orientation of the box. possible values: $orientation
         prop(orientation: as_string) { gtk_widget.set_orientation(parse_orientation(&orientation)?) },
         // @prop space-evenly - space the widgets evenly.
         prop(space_evenly: as_bool = true) { gtk_widget.set_homogeneous(space_evenly) },
+        // @prop max-children - maximum amount of children to show. Additional children are handled according to `overflow`.
+        prop(max_children: as_i32) {
+            *children_limit.borrow_mut() = Some(max_children.max(0) as usize);
+            match &indicator {
+                Some(indicator) => limit_box_children(&gtk_widget, indicator, overflow, *children_limit.borrow()),
+                None => gtk_widget.queue_resize(),
+            }
+        },
+        // @prop timeout - timeout of the command
+        // @prop onreorder - Command to run when the children have been reordered by the user. The placeholder `{}` will be replaced by a json array containing the original indices of the children in their new order.
+        prop(timeout: as_duration = Duration::from_millis(200), onreorder: as_string) {
+            if let Some(reorder) = &reorder {
+                *reorder.onreorder.borrow_mut() = Some((timeout, onreorder));
+            }
+        },
     });
-    Ok(gtk_widget)
+
+    Ok(outer_widget)
+}
+
+/// State of a `box` whose children can be reordered via drag and drop.
+/// Dragging happens on an [`gtk::EventBox`] wrapping the box, as the box itself does not receive any events.
+struct BoxReorder {
+    /// The command to run after the children have been reordered, together with its timeout
+    onreorder: Rc<RefCell<Option<(Duration, String)>>>,
+}
+
+impl BoxReorder {
+    fn setup(gtk_widget: &gtk::Box, event_box: gtk::EventBox) -> Self {
+        let onreorder: Rc<RefCell<Option<(Duration, String)>>> = Rc::new(RefCell::new(None));
+        // The children in the order they where added in, to report the new order as indices into this list.
+        let original_children = Rc::new(RefCell::new(Vec::<gtk::Widget>::new()));
+        let dragged_child = Rc::new(RefCell::new(None::<gtk::Widget>));
+
+        gtk_widget.connect_add(glib::clone!(@strong original_children => move |_, child| {
+            original_children.borrow_mut().push(child.clone());
+        }));
+        gtk_widget.connect_remove(glib::clone!(@strong original_children => move |_, child| {
+            original_children.borrow_mut().retain(|x| x != child);
+        }));
+
+        let target_entry = TargetEntry::new("eww-box-reorder", gtk::TargetFlags::SAME_WIDGET, 0);
+        event_box.drag_source_set(ModifierType::BUTTON1_MASK, &[target_entry.clone()], gdk::DragAction::MOVE);
+        event_box.drag_dest_set(DestDefaults::ALL, &[target_entry], gdk::DragAction::MOVE);
+
+        event_box.connect_button_press_event(
+            glib::clone!(@weak gtk_widget, @strong dragged_child => @default-return gtk::Inhibit(false), move |_, evt| {
+                let (x, y) = evt.position();
+                *dragged_child.borrow_mut() = box_child_at(&gtk_widget, x as i32, y as i32);
+                gtk::Inhibit(false)
+            }),
+        );
+        event_box.connect_drag_data_get(|_, _, data, _, _| {
+            data.set_text("");
+        });
+        event_box.connect_drag_data_received(glib::clone!(@weak gtk_widget, @strong dragged_child, @strong original_children, @strong onreorder => move |_, _, x, y, _, _, _| {
+            let dragged_child = dragged_child.borrow_mut().take();
+            let target_child = box_child_at(&gtk_widget, x, y);
+            if let (Some(dragged_child), Some(target_child)) = (dragged_child, target_child) {
+                if dragged_child == target_child {
+                    return;
+                }
+                let target_position = gtk_widget.children().iter().position(|x| x == &target_child).unwrap_or_default();
+                gtk_widget.reorder_child(&dragged_child, target_position as i32);
+
+                if let Some((timeout, onreorder)) = &*onreorder.borrow() {
+                    let original_children = original_children.borrow();
+                    let order = gtk_widget
+                        .children()
+                        .iter()
+                        .filter_map(|child| original_children.iter().position(|x| x == child))
+                        .join(",");
+                    run_command(&gtk_widget, *timeout, onreorder, &[format!("[{}]", order)]);
+                }
+            }
+        }));
+
+        BoxReorder { onreorder }
+    }
+}
+
+/// Get the visible child of the box at the given position along the orientation of the box.
+/// If the position is past the last child, the last child is returned.
+fn box_child_at(gtk_widget: &gtk::Box, x: i32, y: i32) -> Option<gtk::Widget> {
+    let children = gtk_widget
+        .children()
+        .into_iter()
+        .filter(|child| child.is_visible() && gtk_widget.query_child_packing(child).3 == gtk::PackType::Start)
+        .collect_vec();
+    let is_before_end = |child: &gtk::Widget| {
+        let allocation = child.allocation();
+        match gtk_widget.orientation() {
+            gtk::Orientation::Vertical => y < allocation.y + allocation.height,
+            _ => x < allocation.x + allocation.width,
+        }
+    };
+    children.iter().find(|child| is_before_end(child)).or_else(|| children.last()).cloned()
+}
+
+/// The separators placed across a `box` with `:divider` between every two of its children.
+/// Only these are removed when the children change, such that separators that are children of the box themselves are kept.
+#[derive(Clone, Default)]
+struct BoxDividers {
+    dividers: Rc<RefCell<Vec<gtk::Separator>>>,
+    /// Set while the dividers are being changed, such that the resulting signals are ignored.
+    updating: Rc<Cell<bool>>,
+}
+
+impl BoxDividers {
+    /// Keep the dividers of the box up to date whenever its children are added, removed or reordered.
+    fn setup(gtk_widget: &gtk::Box) {
+        let dividers = BoxDividers::default();
+        gtk_widget.connect_add(glib::clone!(@strong dividers => move |gtk_widget, child| {
+            if dividers.updating.get() {
+                return;
+            }
+            // Reordering a child, i.e. in a `for` with a `:key`, moves it across the dividers.
+            let gtk_box = gtk_widget.clone();
+            child.connect_child_notify(Some("position"), glib::clone!(@strong dividers, @weak gtk_box => move |child, _| {
+                if child.parent().as_ref() == Some(gtk_box.upcast_ref()) {
+                    dividers.update(&gtk_box);
+                }
+            }));
+            dividers.update(gtk_widget);
+        }));
+        gtk_widget.connect_remove(glib::clone!(@strong dividers => move |gtk_widget, _| {
+            dividers.update(gtk_widget);
+        }));
+        gtk_widget.connect_orientation_notify(move |gtk_widget| {
+            dividers.update(gtk_widget);
+        });
+    }
+
+    /// Recreate the dividers, placing one between every two children of the box.
+    fn update(&self, gtk_widget: &gtk::Box) {
+        if self.updating.replace(true) {
+            return;
+        }
+        let mut dividers = self.dividers.borrow_mut();
+        for divider in dividers.drain(..) {
+            gtk_widget.remove(&divider);
+        }
+        let orientation = match gtk_widget.orientation() {
+            gtk::Orientation::Vertical => gtk::Orientation::Horizontal,
+            _ => gtk::Orientation::Vertical,
+        };
+        for index in 1..gtk_widget.children().len() {
+            let divider = gtk::Separator::new(orientation);
+            divider.style_context().add_class("divider");
+            gtk_widget.add(&divider);
+            gtk_widget.reorder_child(&divider, (2 * index - 1) as i32);
+            divider.show();
+            dividers.push(divider);
+        }
+        self.updating.set(false);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoxOverflow {
+    Hide,
+    Ellipsis,
+    Scroll,
+}
+
+/// @var overflow - "hide", "ellipsis-widget", "scroll"
+fn parse_box_overflow(o: &str) -> Result<BoxOverflow> {
+    enum_parse! { "overflow", o,
+        "hide" => BoxOverflow::Hide,
+        "ellipsis-widget" | "ellipsis" => BoxOverflow::Ellipsis,
+        "scroll" => BoxOverflow::Scroll,
+    }
+}
+
+/// Hide all children of the box exceeding `max_children`, showing the given indicator label in their place.
+fn limit_box_children(gtk_widget: &gtk::Box, indicator: &gtk::Label, overflow: BoxOverflow, max_children: Option<usize>) {
+    let indicator_widget: &gtk::Widget = indicator.upcast_ref();
+    let children = gtk_widget.children().into_iter().filter(|w| w != indicator_widget).collect_vec();
+    let max_children = max_children.unwrap_or(children.len());
+    for (index, child) in children.iter().enumerate() {
+        if index < max_children {
+            if child.is_no_show_all() {
+                child.set_no_show_all(false);
+                child.show_all();
+            }
+        } else {
+            child.set_no_show_all(true);
+            child.hide();
+        }
+    }
+    let hidden_count = children.len().saturating_sub(max_children);
+    if hidden_count > 0 {
+        match overflow {
+            BoxOverflow::Ellipsis => indicator.set_text("…"),
+            _ => indicator.set_text(&format!("+{}", hidden_count)),
+        }
+        indicator.show();
+    } else {
+        indicator.hide();
+    }
+}
+
+/// Limit the size of the scrolled window wrapping a box to the extent of the first `max_children` children.
+fn update_box_scroll_extent(scrolled_window: &gtk::ScrolledWindow, gtk_widget: &gtk::Box, max_children: Option<usize>) {
+    let children = gtk_widget.children();
+    let last_visible = match max_children {
+        Some(max) if max > 0 && max < children.len() => children.get(max - 1),
+        _ => None,
+    };
+    let extent = match last_visible {
+        Some(last_visible) => {
+            let (child, container) = (last_visible.allocation(), gtk_widget.allocation());
+            match gtk_widget.orientation() {
+                gtk::Orientation::Vertical => child.y + child.height - container.y,
+                _ => child.x + child.width - container.x,
+            }
+        }
+        None => -1,
+    };
+    match gtk_widget.orientation() {
+        gtk::Orientation::Vertical if scrolled_window.max_content_height() != extent => {
+            scrolled_window.set_max_content_height(extent)
+        }
+        gtk::Orientation::Horizontal if scrolled_window.max_content_width() != extent => {
+            scrolled_window.set_max_content_width(extent)
+        }
+        _ => {}
+    }
 }
 
 const WIDGET_NAME_OVERLAY: &str = "overlay";
@@ -527,7 +1243,19 @@ const WIDGET_NAME_OVERLAY: &str = "overlay";
 fn build_gtk_overlay(bargs: &mut BuilderArgs) -> Result<gtk::Overlay> {
     let gtk_widget = gtk::Overlay::new();
 
-    // no def_widget because this widget has no props.
+    let clip_radius = Rc::new(Cell::new(None));
+    if bargs.widget_use.attrs.attrs.contains_key("clip") {
+        connect_rounded_clip(gtk_widget.upcast_ref(), clip_radius.clone());
+    }
+
+    def_widget!(bargs, _g, gtk_widget, {
+        // @prop clip - clip the children of the overlay to its bounds, with corners rounded by `radius`. This is more expensive to draw than a plain overlay.
+        // @prop radius - radius of the corners when clipping the children, in pixels
+        prop(clip: as_bool = false, radius: as_f64 = 0) {
+            clip_radius.set(clip.then(|| radius.max(0f64)));
+            gtk_widget.queue_draw();
+        },
+    });
 
     match bargs.widget_use.children.len().cmp(&1) {
         Ordering::Less => {
@@ -557,6 +1285,39 @@ fn build_gtk_overlay(bargs: &mut BuilderArgs) -> Result<gtk::Overlay> {
     }
 }
 
+/// Clip everything drawn by the given container to its allocation, with the corners rounded by the radius in `clip_radius`.
+/// Nothing is clipped while `clip_radius` is `None`.
+///
+/// The clip is applied in a handler that runs before the default draw handler of the container,
+/// so every redraw of the container and its children has to go through the clip path.
+/// Thus, this is only set up for containers that actually use `:clip`.
+fn connect_rounded_clip(gtk_widget: &gtk::Widget, clip_radius: Rc<Cell<Option<f64>>>) {
+    gtk_widget.connect_draw(move |gtk_widget, cr| {
+        if let Some(radius) = clip_radius.get() {
+            let width = gtk_widget.allocated_width() as f64;
+            let height = gtk_widget.allocated_height() as f64;
+            rounded_rectangle(cr, width, height, radius);
+            cr.clip();
+        }
+        gtk::Inhibit(false)
+    });
+    // The clip path depends on the size of the container, so it needs to be redrawn completely when that changes.
+    gtk_widget.connect_size_allocate(|gtk_widget, _| gtk_widget.queue_draw());
+}
+
+/// Add a rectangle from the origin with the given size and corners rounded by `radius` to the current path.
+/// The radius is limited to half of the shorter side of the rectangle.
+fn rounded_rectangle(cr: &cairo::Context, width: f64, height: f64, radius: f64) {
+    use std::f64::consts::{FRAC_PI_2, PI};
+    let radius = radius.min(width / 2f64).min(height / 2f64).max(0f64);
+    cr.new_sub_path();
+    cr.arc(width - radius, radius, radius, -FRAC_PI_2, 0f64);
+    cr.arc(width - radius, height - radius, radius, 0f64, FRAC_PI_2);
+    cr.arc(radius, height - radius, radius, FRAC_PI_2, PI);
+    cr.arc(radius, radius, radius, PI, PI + FRAC_PI_2);
+    cr.close_path();
+}
+
 const WIDGET_NAME_CENTERBOX: &str = "centerbox";
 /// @widget centerbox
 /// @desc a box that must contain exactly three children, which will be layed out at the start, center and end of the container.
@@ -606,6 +1367,27 @@ fn build_center_box(bargs: &mut BuilderArgs) -> Result<gtk::Box> {
     }
 }
 
+const WIDGET_NAME_SEPARATOR: &str = "separator";
+/// @widget separator
+/// @desc a thin line separating other widgets. Its color and thickness can also be styled via CSS.
+fn build_gtk_separator(bargs: &mut BuilderArgs) -> Result<gtk::Separator> {
+    let gtk_widget = gtk::Separator::new(gtk::Orientation::Horizontal);
+    def_widget!(bargs, _g, gtk_widget, {
+        // @prop orientation - orientation of the separator. possible values: $orientation
+        // @prop thickness - thickness of the separator, in pixels
+        prop(orientation: as_string = "horizontal", thickness: as_i32?) {
+            let orientation = parse_orientation(&orientation)?;
+            gtk_widget.set_orientation(orientation);
+            let thickness = thickness.map(|thickness| thickness.max(0)).unwrap_or(-1);
+            match orientation {
+                gtk::Orientation::Vertical => gtk_widget.set_size_request(thickness, -1),
+                _ => gtk_widget.set_size_request(-1, thickness),
+            }
+        },
+    });
+    Ok(gtk_widget)
+}
+
 const WIDGET_NAME_SCROLL: &str = "scroll";
 /// @widget scroll
 /// @desc a container with a single child that can scroll.
@@ -670,10 +1452,10 @@ fn build_gtk_event_box(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
         prop(timeout: as_duration = Duration::from_millis(200), onscroll: as_string) {
             gtk_widget.add_events(gdk::EventMask::SCROLL_MASK);
             gtk_widget.add_events(gdk::EventMask::SMOOTH_SCROLL_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_scroll_event(move |_, evt| {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_scroll_event(move |gtk_widget, evt| {
                 let delta = evt.delta().1;
                 if delta != 0f64 { // Ignore the first event https://bugzilla.gnome.org/show_bug.cgi?id=675959
-                    run_command(timeout, &onscroll, &[if delta < 0f64 { "up" } else { "down" }]);
+                    run_command(gtk_widget, timeout, &onscroll, &[if delta < 0f64 { "up" } else { "down" }]);
                 }
                 gtk::Inhibit(false)
             }));
@@ -682,9 +1464,9 @@ fn build_gtk_event_box(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
         // @prop onhover - event to execute when the user hovers over the widget
         prop(timeout: as_duration = Duration::from_millis(200), onhover: as_string) {
             gtk_widget.add_events(gdk::EventMask::ENTER_NOTIFY_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_enter_notify_event(move |_, evt| {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_enter_notify_event(move |gtk_widget, evt| {
                 if evt.detail() != NotifyType::Inferior {
-                    run_command(timeout, &onhover, &[evt.position().0, evt.position().1]);
+                    run_command(gtk_widget, timeout, &onhover, &[evt.position().0, evt.position().1]);
                 }
                 gtk::Inhibit(false)
             }));
@@ -693,9 +1475,9 @@ fn build_gtk_event_box(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
         // @prop onhoverlost - event to execute when the user losts hovers over the widget
         prop(timeout: as_duration = Duration::from_millis(200), onhoverlost: as_string) {
             gtk_widget.add_events(gdk::EventMask::LEAVE_NOTIFY_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_leave_notify_event(move |_, evt| {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_leave_notify_event(move |gtk_widget, evt| {
                 if evt.detail() != NotifyType::Inferior {
-                    run_command(timeout, &onhoverlost, &[evt.position().0, evt.position().1]);
+                    run_command(gtk_widget, timeout, &onhoverlost, &[evt.position().0, evt.position().1]);
                 }
                 gtk::Inhibit(false)
             }));
@@ -736,11 +1518,11 @@ fn build_gtk_event_box(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
                 ],
                 gdk::DragAction::COPY,
             );
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_drag_data_received(move |_, _, _x, _y, selection_data, _target_type, _timestamp| {
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_drag_data_received(move |gtk_widget, _, _x, _y, selection_data, _target_type, _timestamp| {
                 if let Some(data) = selection_data.uris().first(){
-                    run_command(timeout, &ondropped, &[data.to_string(), "file".to_string()]);
+                    run_command(gtk_widget, timeout, &ondropped, &[data.to_string(), "file".to_string()]);
                 } else if let Some(data) = selection_data.text(){
-                    run_command(timeout, &ondropped, &[data.to_string(), "text".to_string()]);
+                    run_command(gtk_widget, timeout, &ondropped, &[data.to_string(), "text".to_string()]);
                 }
             }));
         },
@@ -777,20 +1559,29 @@ fn build_gtk_event_box(bargs: &mut BuilderArgs) -> Result<gtk::EventBox> {
         prop(
             // @prop timeout - timeout of the command
             timeout: as_duration = Duration::from_millis(200),
-            // @prop onclick - a command that get's run when the button is clicked
+            // @prop onclick - a command that get's run when the button is clicked. The `{fraction_x}` and `{fraction_y}` placeholders are replaced by the position of the click, as a fraction of the widget's width and height.
             onclick: as_string = "",
             // @prop onmiddleclick - a command that get's run when the button is middleclicked
             onmiddleclick: as_string = "",
             // @prop onrightclick - a command that get's run when the button is rightclicked
-            onrightclick: as_string = ""
+            onrightclick: as_string = "",
+            // @prop no-concurrent - if true, clicks are ignored while the command of a previous click is still running
+            no_concurrent: as_bool = false
         ) {
             gtk_widget.add_events(gdk::EventMask::BUTTON_PRESS_MASK);
-            connect_signal_handler!(gtk_widget, gtk_widget.connect_button_press_event(move |_, evt| {
-                match evt.button() {
-                    1 => run_command(timeout, &onclick, &[] as &[&str]),
-                    2 => run_command(timeout, &onmiddleclick, &[] as &[&str]),
-                    3 => run_command(timeout, &onrightclick, &[] as &[&str]),
-                    _ => {},
+            let running = Arc::new(AtomicBool::new(false));
+            connect_signal_handler!(gtk_widget, gtk_widget.connect_button_press_event(move |gtk_widget, evt| {
+                let cmd = match evt.button() {
+                    1 => &onclick,
+                    2 => &onmiddleclick,
+                    3 => &onrightclick,
+                    _ => return gtk::Inhibit(false),
+                };
+                let cmd = replace_click_position_placeholders(cmd, gtk_widget, evt);
+                if no_concurrent {
+                    run_command_exclusive(gtk_widget, timeout, &cmd, &[] as &[&str], &running);
+                } else {
+                    run_command(gtk_widget, timeout, &cmd, &[] as &[&str]);
                 }
                 gtk::Inhibit(false)
             }));
@@ -806,6 +1597,16 @@ const WIDGET_NAME_LABEL: &str = "label";
 fn build_gtk_label(bargs: &mut BuilderArgs) -> Result<gtk::Label> {
     let gtk_widget = gtk::Label::new(None);
 
+    // @prop auto-escape - escape the values interpolated into `markup`, such that only the markup written in the config itself is interpreted as markup. Default: false.
+    // This can not reference any variables.
+    let auto_escape = bargs.widget_use.attrs.primitive_optional::<bool, _>("auto-escape")?.unwrap_or(false);
+    bargs.unhandled_attrs.retain(|attr| attr.0 != "auto-escape");
+    if auto_escape {
+        if let Some(markup) = bargs.widget_use.attrs.attrs.get_mut("markup") {
+            markup.value = Ast::SimplExpr(markup.value.span(), escape_interpolated_markup(markup.value.as_simplexpr()?));
+        }
+    }
+
     def_widget!(bargs, _g, gtk_widget, {
         // @prop text - the text to display
         // @prop limit-width - maximum count of characters to display
@@ -826,6 +1627,8 @@ fn build_gtk_label(bargs: &mut BuilderArgs) -> Result<gtk::Label> {
         prop(markup: as_string) { gtk_widget.set_markup(&markup); },
         // @prop wrap - Wrap the text. This mainly makes sense if you set the width of this widget.
         prop(wrap: as_bool) { gtk_widget.set_line_wrap(wrap) },
+        // @prop selectable - Allow the user to select and copy the text. Note that a selectable label consumes mouse clicks, so an enclosing eventbox will not receive `onclick` events on top of it.
+        prop(selectable: as_bool = false) { gtk_widget.set_selectable(selectable) },
         // @prop angle - the angle of rotation for the label (between 0 - 360)
         prop(angle: as_f64 = 0) { gtk_widget.set_angle(angle) },
         // @prop xalign - the alignment of the label text on the x axis (between 0 - 1, 0 -> left, 0.5 -> center, 1 -> right)
@@ -836,6 +1639,15 @@ fn build_gtk_label(bargs: &mut BuilderArgs) -> Result<gtk::Label> {
     Ok(gtk_widget)
 }
 
+/// Escape the parts of a markup expression that are interpolated into it, keeping the literal parts as they are.
+fn escape_interpolated_markup(expr: SimplExpr) -> SimplExpr {
+    match expr {
+        SimplExpr::Literal(_) => expr,
+        SimplExpr::Concat(span, parts) => SimplExpr::Concat(span, parts.into_iter().map(escape_interpolated_markup).collect()),
+        other => SimplExpr::FunctionCall(other.span(), "escape_markup".to_string(), vec![other]),
+    }
+}
+
 const WIDGET_NAME_LITERAL: &str = "literal";
 /// @widget literal
 /// @desc A widget that allows you to render arbitrary yuck.
@@ -912,6 +1724,7 @@ fn build_gtk_calendar(bargs: &mut BuilderArgs) -> Result<gtk::Calendar> {
             connect_signal_handler!(gtk_widget, gtk_widget.connect_day_selected(move |w| {
                 log::warn!("BREAKING CHANGE: The date is now provided via three values, set by the placeholders {{0}}, {{1}} and {{2}}. If you're currently using the onclick date, you will need to change this.");
                 run_command(
+                    w,
                     timeout,
                     &onclick,
                     &[w.day(), w.month(), w.year()]
@@ -947,7 +1760,7 @@ fn build_transform(bargs: &mut BuilderArgs) -> Result<Transform> {
 
 const WIDGET_NAME_CIRCULAR_PROGRESS: &str = "circular-progress";
 /// @widget circular-progress
-/// @desc A widget that displays a circular progress bar
+/// @desc A widget that displays a circular progress bar. It may contain a single child, which is shown inside of the ring.
 fn build_circular_progress_bar(bargs: &mut BuilderArgs) -> Result<CircProg> {
     let w = CircProg::new();
     def_widget!(bargs, _g, w, {
@@ -959,18 +1772,240 @@ fn build_circular_progress_bar(bargs: &mut BuilderArgs) -> Result<CircProg> {
         prop(thickness: as_f64) { w.set_property("thickness", thickness)?; },
         // @prop clockwise - wether the progress bar spins clockwise or counter clockwise
         prop(clockwise: as_bool) { w.set_property("clockwise", &clockwise)?; },
+        // @prop ticks - the number of tick marks drawn around the ring. Default: 0.
+        prop(ticks: as_i32 = 0) { w.set_property("ticks", &(ticks.max(0) as u32))?; },
+        // @prop tick-labels - a JSON array of labels that are drawn inside of the ring next to the ticks, starting at the first tick
+        prop(tick_labels: as_json_array) {
+            w.set_tick_labels(
+                tick_labels.into_iter().map(|label| label.as_str().map(str::to_string).unwrap_or_else(|| label.to_string())).collect(),
+            );
+        },
     });
     Ok(w)
 }
 
+const WIDGET_NAME_TREE: &str = "tree";
+const TREE_NODE_VARIABLE: ChildVariable = ChildVariable { widget_name: WIDGET_NAME_TREE, name_attr: "var", default_name: "node" };
+/// @widget tree
+/// @desc A widget that renders nested data as a tree of expandable nodes. Its single child is used as the template for every node.
+fn build_tree(bargs: &mut BuilderArgs) -> Result<gtk::Box> {
+    let gtk_widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+    // The template is built for every node below, so it must not be populated into the tree directly.
+    let mut children = std::mem::take(&mut bargs.widget_use.children);
+    let body = match children.len() {
+        1 => children.remove(0),
+        _ => {
+            return Err(DiagError::new(gen_diagnostic!(
+                "tree must contain exactly one element, which is used as the template for its nodes",
+                bargs.widget_use.span
+            ))
+            .into())
+        }
+    };
+
+    // @prop var - name of the variable the current node is available as within the template. Defaults to `node`.
+    // This can not reference any variables.
+    let var_name = bargs
+        .widget_use
+        .attrs
+        .primitive_optional::<String, _>(TREE_NODE_VARIABLE.name_attr)?
+        .unwrap_or_else(|| TREE_NODE_VARIABLE.default_name.to_string());
+    bargs.unhandled_attrs.retain(|a| a.0 != TREE_NODE_VARIABLE.name_attr);
+
+    // Kept across updates of the nodes, so that nodes with an `id` stay expanded or collapsed.
+    let expansion_states = Rc::new(RefCell::new(HashMap::new()));
+    let widget_defs = bargs.widget_defs.clone();
+    let calling_scope = bargs.calling_scope;
+    let custom_widget_invocation = bargs.custom_widget_invocation.clone();
+    def_widget!(bargs, graph, gtk_widget, {
+        // @prop nodes - json array of nodes. Each node may contain a `children` array of further nodes, and an `id` which is used to keep the node expanded or collapsed when the nodes change.
+        // @prop expanded - whether nodes with children should initially be expanded
+        // @prop indent - indentation of child nodes in pixels
+        prop(nodes: as_json_array, expanded: as_bool = false, indent: as_i32 = 16) {
+            for old_child in gtk_widget.children() {
+                unsafe { old_child.destroy() };
+            }
+            let template = TreeTemplate {
+                body: body.clone(),
+                var_name: VarName(var_name.clone()),
+                expanded,
+                indent,
+                expansion_states: expansion_states.clone(),
+                custom_widget_invocation: custom_widget_invocation.clone(),
+            };
+            build_tree_nodes(graph, widget_defs.clone(), calling_scope, &template, &nodes, gtk_widget.upcast_ref(), 0)?;
+        },
+    });
+    Ok(gtk_widget)
+}
+
+const WIDGET_NAME_SWITCH: &str = "switch";
+/// @widget switch
+/// @desc A widget that shows the first of its `(case :when {condition} child)` elements whose condition is true, or the child of its `(default child)` element if none is. Transitions between the branches are animated. Only the visible branch is built.
+fn build_switch(bargs: &mut BuilderArgs) -> Result<gtk::Stack> {
+    let stack = gtk::Stack::new();
+    stack.set_homogeneous(false);
+    stack.set_interpolate_size(true);
+
+    // The cases are built lazily below, so they must not be populated into the stack directly.
+    let branches = parse_switch_branches(std::mem::take(&mut bargs.widget_use.children))?;
+
+    // Once the transition to a new branch has finished, the previous branch is torn down.
+    stack.connect_transition_running_notify(|stack| {
+        if !stack.is_transition_running() {
+            remove_hidden_switch_branches(stack);
+        }
+    });
+
+    def_widget!(bargs, _g, stack, {
+        // @prop transition - the name of the transition between branches. Possible values: $transition
+        // @prop duration - the duration of the transition between branches
+        prop(transition: as_string = "crossfade", duration: as_duration = Duration::from_millis(200)) {
+            stack.set_transition_type(parse_stack_transition(&transition)?);
+            stack.set_transition_duration(duration.as_millis() as u32);
+        },
+    });
+
+    let needed_variables =
+        branches.iter().filter_map(|(condition, _)| condition.as_ref()).flat_map(|x| x.collect_var_refs()).unique().collect();
+    let widget_defs = bargs.widget_defs.clone();
+    let calling_scope = bargs.calling_scope;
+    let custom_widget_invocation = bargs.custom_widget_invocation.clone();
+    // The index of the branch that is currently shown, or `Some(None)` if no branch matches.
+    let active_branch = Cell::new(None);
+    bargs.scope_graph.register_listener(
+        calling_scope,
+        Listener {
+            needed_variables,
+            f: Box::new({
+                let stack = stack.downgrade();
+                move |graph, values| {
+                    let stack = match stack.upgrade() {
+                        Some(stack) => stack,
+                        None => return Ok(()),
+                    };
+                    let matching_branch = first_matching_switch_branch(&branches, &values)?;
+                    if active_branch.get() == Some(matching_branch) {
+                        return Ok(());
+                    }
+                    let branch_widget = match matching_branch {
+                        Some(index) => build_switch_branch(
+                            graph,
+                            widget_defs.clone(),
+                            calling_scope,
+                            index,
+                            branches[index].1.clone(),
+                            custom_widget_invocation.clone(),
+                        )?,
+                        // Without a matching branch, transition to an empty placeholder
+                        None => gtk::Box::new(gtk::Orientation::Horizontal, 0).upcast(),
+                    };
+                    active_branch.set(Some(matching_branch));
+                    stack.add(&branch_widget);
+                    branch_widget.show();
+                    stack.set_visible_child(&branch_widget);
+                    if !stack.is_transition_running() {
+                        remove_hidden_switch_branches(&stack);
+                    }
+                    Ok(())
+                }
+            }),
+        },
+    )?;
+    Ok(stack)
+}
+
+/// Parse the `case` and `default` elements of a `switch` widget into the conditions and bodies of its branches.
+/// The `default` element, which has no condition, must be the last element.
+fn parse_switch_branches(children: Vec<WidgetUse>) -> Result<Vec<(Option<SimplExpr>, WidgetUse)>> {
+    let branch_count = children.len();
+    children
+        .into_iter()
+        .enumerate()
+        .map(|(index, child)| {
+            let mut branch = match child {
+                WidgetUse::Basic(branch) if branch.name == "case" || branch.name == "default" => branch,
+                other => {
+                    return Err(DiagError::new(gen_diagnostic!(
+                        "switch may only contain `case` and `default` elements",
+                        other.span()
+                    ))
+                    .into())
+                }
+            };
+            let body = match branch.children.len() {
+                1 => branch.children.remove(0),
+                _ => {
+                    return Err(DiagError::new(gen_diagnostic!(
+                        format!("`{}` must contain exactly one element", branch.name),
+                        branch.span
+                    ))
+                    .into())
+                }
+            };
+            if branch.name == "default" {
+                if index + 1 != branch_count {
+                    return Err(
+                        DiagError::new(gen_diagnostic!("`default` must be the last element of a switch", branch.span)).into()
+                    );
+                }
+                Ok((None, body))
+            } else {
+                let condition = branch.attrs.ast_required::<SimplExpr>("when")?;
+                Ok((Some(condition), body))
+            }
+        })
+        .collect()
+}
+
+/// Get the index of the first branch of a `switch` widget whose condition is true, treating a missing condition as true.
+fn first_matching_switch_branch(
+    branches: &[(Option<SimplExpr>, WidgetUse)],
+    values: &HashMap<VarName, simplexpr::dynval::DynVal>,
+) -> Result<Option<usize>> {
+    for (index, (condition, _)) in branches.iter().enumerate() {
+        let matches = match condition {
+            Some(condition) => condition.eval(values)?.as_bool()?,
+            None => true,
+        };
+        if matches {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Destroy all branches of a `switch` widget except for the visible one, removing their scopes.
+fn remove_hidden_switch_branches(stack: &gtk::Stack) {
+    let visible_child = stack.visible_child();
+    for child in stack.children() {
+        if Some(&child) != visible_child.as_ref() {
+            unsafe { child.destroy() };
+        }
+    }
+}
+
 const WIDGET_NAME_GRAPH: &str = "graph";
 /// @widget graph
 /// @desc A widget that displays a graph showing how a given value changes over time
 fn build_graph(bargs: &mut BuilderArgs) -> Result<super::graph::Graph> {
     let w = super::graph::Graph::new();
     def_widget!(bargs, _g, w, {
-        // @prop value - the value, between 0 - 100
+        // @prop value - the value, between `min` and `max`. Values below the `baseline` are drawn below it.
         prop(value: as_f64) { w.set_property("value", &value)?; },
+        // @prop values - a JSON array of numbers to plot directly, spread evenly across the graph, instead of the history of `value`
+        prop(values: as_json_array?) {
+            let values = values
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|value| value.as_f64().with_context(|| format!("Graph value {} is not a number", value)))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?;
+            w.set_values(values);
+        },
         // @prop thickness - the thickness of the line
         prop(thickness: as_f64) { w.set_property("thickness", &thickness)?; },
         // @prop time-range - the range of time to show
@@ -986,7 +2021,9 @@ fn build_graph(bargs: &mut BuilderArgs) -> Result<super::graph::Graph> {
             w.set_property("min", &min)?;
             w.set_property("max", &max)?;
         },
-        // @prop dynamic - whether the y range should dynamically change based on value
+        // @prop baseline - the value that the area between it and the line is filled towards, using the background-color. Defaults to 0.
+        prop(baseline: as_f64 = 0) { w.set_property("baseline", &baseline)?; },
+        // @prop dynamic - whether the y range should dynamically extend to include all shown values, including negative ones
         prop(dynamic: as_bool) { w.set_property("dynamic", &dynamic)?; },
         // @prop line-style - changes the look of the edges in the graph. Values: "miter" (default), "round",
         // "bevel"
@@ -1028,6 +2065,17 @@ fn parse_transition(t: &str) -> Result<gtk::RevealerTransitionType> {
     }
 }
 
+fn parse_stack_transition(t: &str) -> Result<gtk::StackTransitionType> {
+    enum_parse! { "transition", t,
+        "slideright" => gtk::StackTransitionType::SlideRight,
+        "slideleft" => gtk::StackTransitionType::SlideLeft,
+        "slideup" => gtk::StackTransitionType::SlideUp,
+        "slidedown" => gtk::StackTransitionType::SlideDown,
+        "fade" | "crossfade" => gtk::StackTransitionType::Crossfade,
+        "none" => gtk::StackTransitionType::None,
+    }
+}
+
 /// @var alignment - "fill", "baseline", "center", "start", "end"
 fn parse_align(o: &str) -> Result<gtk::Align> {
     enum_parse! { "alignment", o,
@@ -1053,3 +2101,98 @@ fn connect_first_map<W: IsA<gtk::Widget>, F: Fn(&W) + 'static>(widget: &W, func:
         }
     }));
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use simplexpr::dynval::DynVal;
+
+    #[test]
+    fn test_escape_interpolated_markup() {
+        let span = eww_shared_util::Span::DUMMY;
+        let markup = SimplExpr::Concat(
+            span,
+            vec![SimplExpr::synth_string("<b>"), SimplExpr::var_ref(span, "title"), SimplExpr::synth_string("</b>")],
+        );
+        let expected = SimplExpr::Concat(
+            span,
+            vec![
+                SimplExpr::synth_string("<b>"),
+                SimplExpr::FunctionCall(span, "escape_markup".to_string(), vec![SimplExpr::var_ref(span, "title")]),
+                SimplExpr::synth_string("</b>"),
+            ],
+        );
+        assert_eq!(escape_interpolated_markup(markup), expected);
+    }
+
+    #[test]
+    fn test_progress_value_is_clamped() {
+        assert_eq!(progress_fraction(150.0), 1.0);
+        assert_eq!(progress_fraction(-20.0), 0.0);
+        assert_eq!(progress_fraction(50.0), 0.5);
+    }
+
+    #[test]
+    fn test_filled_segments() {
+        assert_eq!(filled_segments(progress_fraction(0.0), 5), 0);
+        assert_eq!(filled_segments(progress_fraction(50.0), 5), 3);
+        assert_eq!(filled_segments(progress_fraction(79.0), 5), 4);
+        assert_eq!(filled_segments(progress_fraction(100.0), 5), 5);
+        assert_eq!(filled_segments(progress_fraction(150.0), 5), 5);
+    }
+
+    #[test]
+    fn test_size_request() {
+        assert_eq!(size_request(Some(100)), 100);
+        assert_eq!(size_request(Some(-20)), -1);
+        assert_eq!(size_request(None), -1);
+    }
+
+    fn parse_switch_children(input: &str) -> Vec<WidgetUse> {
+        let ast = yuck::parser::parse_string(0, input).unwrap();
+        match WidgetUse::from_ast(ast).unwrap() {
+            WidgetUse::Basic(switch) => switch.children,
+            _ => panic!("Expected a basic widget"),
+        }
+    }
+
+    #[test]
+    fn test_parse_switch_branches() {
+        let branches = parse_switch_branches(parse_switch_children(
+            r#"(switch (case :when {a} (label :text "a")) (case :when {b} "b") (default "c"))"#,
+        ))
+        .unwrap();
+        assert_eq!(branches.len(), 3);
+        assert_eq!(branches[0].0.as_ref().unwrap().collect_var_refs(), vec![VarName::from("a")]);
+        assert_eq!(branches[1].0.as_ref().unwrap().collect_var_refs(), vec![VarName::from("b")]);
+        assert!(branches[2].0.is_none());
+
+        assert!(parse_switch_branches(parse_switch_children(r#"(switch (default "a") (case :when {b} "b"))"#)).is_err());
+        assert!(parse_switch_branches(parse_switch_children(r#"(switch (case :when {a} "a" "b"))"#)).is_err());
+        assert!(parse_switch_branches(parse_switch_children(r#"(switch (case "a"))"#)).is_err());
+        assert!(parse_switch_branches(parse_switch_children(r#"(switch (label :text "a"))"#)).is_err());
+    }
+
+    #[test]
+    fn test_first_matching_switch_branch() {
+        let branches =
+            parse_switch_branches(parse_switch_children(r#"(switch (case :when {a} "a") (case :when {b} "b") (default "c"))"#))
+                .unwrap();
+        let values =
+            |a: bool, b: bool| HashMap::from([(VarName::from("a"), DynVal::from(a)), (VarName::from("b"), DynVal::from(b))]);
+        assert_eq!(first_matching_switch_branch(&branches, &values(true, true)).unwrap(), Some(0));
+        assert_eq!(first_matching_switch_branch(&branches, &values(false, true)).unwrap(), Some(1));
+        assert_eq!(first_matching_switch_branch(&branches, &values(false, false)).unwrap(), Some(2));
+
+        let branches = parse_switch_branches(parse_switch_children(r#"(switch (case :when {a} "a"))"#)).unwrap();
+        assert_eq!(first_matching_switch_branch(&branches, &values(false, false)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_clamp_to_range() {
+        assert_eq!(clamp_to_range(150.0, 0.0, 100.0), 100.0);
+        assert_eq!(clamp_to_range(-5.0, 0.0, 100.0), 0.0);
+        assert_eq!(clamp_to_range(42.0, 0.0, 100.0), 42.0);
+        assert_eq!(clamp_to_range(42.0, 100.0, 0.0), 0.0);
+    }
+}
@@ -36,6 +36,12 @@ pub enum EvalError {
     #[error("Json operation failed: {0}")]
     SerdeError(#[from] serde_json::error::Error),
 
+    #[error("Cannot compute the {0} of an empty array")]
+    EmptyArray(String),
+
+    #[error("Invalid duration `{0}`, expected a duration like `1h30m`")]
+    InvalidDuration(String),
+
     #[error("{1}")]
     Spanned(Span, Box<EvalError>),
 }
@@ -314,6 +320,31 @@ fn call_expr_function(name: &str, args: Vec<DynVal>) -> Result<DynVal, EvalError
             }
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
+        "regex_split" => match args.as_slice() {
+            [string, pattern] => {
+                use serde_json::Value;
+                let string = string.as_string()?;
+                let pattern = regex::Regex::new(&pattern.as_string()?)?;
+                Ok(Value::Array(pattern.split(&string).map(|x| Value::String(x.to_string())).collect()).try_into()?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "lines" => match args.as_slice() {
+            [string] => {
+                use serde_json::Value;
+                let string = string.as_string()?;
+                Ok(Value::Array(string.lines().map(|x| Value::String(x.to_string())).collect()).try_into()?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "words" => match args.as_slice() {
+            [string] => {
+                use serde_json::Value;
+                let string = string.as_string()?;
+                Ok(Value::Array(string.split_whitespace().map(|x| Value::String(x.to_string())).collect()).try_into()?)
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
         "strlength" => match args.as_slice() {
             [string] => Ok(DynVal::from(string.as_string()?.len() as i32)),
             _ => Err(EvalError::WrongArgCount(name.to_string())),
@@ -326,7 +357,315 @@ fn call_expr_function(name: &str, args: Vec<DynVal>) -> Result<DynVal, EvalError
             [json] => Ok(DynVal::from(json.as_json_object()?.len() as i32)),
             _ => Err(EvalError::WrongArgCount(name.to_string())),
         },
+        "count" => match args.as_slice() {
+            [json] => Ok(DynVal::from(json.as_json_array()?.len() as i32)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "sum" => match args.as_slice() {
+            [json] => Ok(DynVal::from(json_array_as_f64s(json)?.into_iter().sum::<f64>())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "avg" => match args.as_slice() {
+            [json] => {
+                let nums = json_array_as_f64s(json)?;
+                if nums.is_empty() {
+                    return Err(EvalError::EmptyArray("average".to_string()));
+                }
+                Ok(DynVal::from(nums.iter().sum::<f64>() / nums.len() as f64))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "min" => match args.as_slice() {
+            [json] => json_array_as_f64s(json)?
+                .into_iter()
+                .reduce(f64::min)
+                .map(DynVal::from)
+                .ok_or_else(|| EvalError::EmptyArray("minimum".to_string())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "max" => match args.as_slice() {
+            [json] => json_array_as_f64s(json)?
+                .into_iter()
+                .reduce(f64::max)
+                .map(DynVal::from)
+                .ok_or_else(|| EvalError::EmptyArray("maximum".to_string())),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "to_number" => match args.as_slice() {
+            [value] => Ok(DynVal::from(value.as_f64()?)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "to_string" => match args.as_slice() {
+            [value] => Ok(DynVal::from(value.as_string()?)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "any" => match args.as_slice() {
+            [json] => {
+                for value in json.as_json_array()? {
+                    if DynVal::from(&value).as_bool()? {
+                        return Ok(DynVal::from(true));
+                    }
+                }
+                Ok(DynVal::from(false))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "all" => match args.as_slice() {
+            [json] => {
+                for value in json.as_json_array()? {
+                    if !DynVal::from(&value).as_bool()? {
+                        return Ok(DynVal::from(false));
+                    }
+                }
+                Ok(DynVal::from(true))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "matches_any" => match args.as_slice() {
+            [json, pattern] => {
+                let pattern = regex::Regex::new(&pattern.as_string()?)?;
+                for value in json.as_json_array()? {
+                    if pattern.is_match(&DynVal::from(&value).as_string()?) {
+                        return Ok(DynVal::from(true));
+                    }
+                }
+                Ok(DynVal::from(false))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "escape_markup" => match args.as_slice() {
+            [string] => Ok(DynVal::from(escape_markup(&string.as_string()?))),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "hash_color" => match args.as_slice() {
+            [string] => Ok(DynVal::from(hash_color(&string.as_string()?, 60.0, 60.0))),
+            [string, saturation, lightness] => {
+                Ok(DynVal::from(hash_color(&string.as_string()?, saturation.as_f64()?, lightness.as_f64()?)))
+            }
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "time_diff" => match args.as_slice() {
+            [a, b] => Ok(DynVal::from(a.as_f64()? - b.as_f64()?)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "duration" => match args.as_slice() {
+            [duration] => Ok(DynVal::from(parse_duration_secs(&duration.as_string()?)?)),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "relative_time" => match args.as_slice() {
+            [epoch] => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|now| now.as_secs_f64())
+                    .unwrap_or_default();
+                Ok(DynVal::from(format_relative_time(now - epoch.as_f64()?)))
+            }
+            [epoch, now] => Ok(DynVal::from(format_relative_time(now.as_f64()? - epoch.as_f64()?))),
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
+        "to_bool" => match args.as_slice() {
+            [value] => match value.as_f64() {
+                Ok(num) => Ok(DynVal::from(num != 0.0)),
+                Err(_) => Ok(DynVal::from(value.as_bool()?)),
+            },
+            _ => Err(EvalError::WrongArgCount(name.to_string())),
+        },
 
         _ => Err(EvalError::UnknownFunction(name.to_string())),
     }
 }
+
+/// Escape the characters that have a special meaning in Pango markup.
+fn escape_markup(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Deterministically map a string to a hex color with the given saturation and lightness, in percent.
+/// The hue is derived from the FNV-1a hash of the string, which, unlike the std hasher, is stable across versions.
+fn hash_color(s: &str, saturation: f64, lightness: f64) -> String {
+    let hash = s.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    hsl_to_hex((hash % 360) as f64, saturation, lightness)
+}
+
+/// Convert a color given as hue in degrees, and saturation and lightness in percent, to a hex color like `#5cd6ce`.
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let saturation = saturation.clamp(0.0, 100.0) / 100.0;
+    let lightness = lightness.clamp(0.0, 100.0) / 100.0;
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let to_byte = |value: f64| ((value + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", to_byte(r), to_byte(g), to_byte(b))
+}
+
+/// Parse a duration like `90s` or `1h30m` into seconds.
+/// The supported units are `ms`, `s`, `m` or `min`, `h` and `d`, and multiple units can be combined.
+fn parse_duration_secs(s: &str) -> Result<f64, EvalError> {
+    let invalid = || EvalError::InvalidDuration(s.to_string());
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut secs = 0.0;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let number = rest[..number_len].parse::<f64>().map_err(|_| invalid())?;
+        rest = &rest[number_len..];
+        let unit_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let unit_secs = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" | "min" => 60.0,
+            "h" => 60.0 * 60.0,
+            "d" => 24.0 * 60.0 * 60.0,
+            _ => return Err(invalid()),
+        };
+        secs += number * unit_secs;
+        rest = rest[unit_len..].trim_start();
+    }
+    Ok(secs)
+}
+
+/// Format the number of seconds that passed since a point in time, like `2m ago`, or `in 3h` for negative numbers.
+fn format_relative_time(secs_ago: f64) -> String {
+    let secs = secs_ago.abs().floor() as u64;
+    let amount = match secs {
+        0 => return "now".to_string(),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    };
+    if secs_ago < 0.0 {
+        format!("in {}", amount)
+    } else {
+        format!("{} ago", amount)
+    }
+}
+
+/// Read a json array of numbers, failing if any of the elements is not numeric.
+fn json_array_as_f64s(value: &DynVal) -> Result<Vec<f64>, EvalError> {
+    Ok(value.as_json_array()?.iter().map(|x| DynVal::from(x).as_f64()).collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval_str(s: &str) -> Result<DynVal, EvalError> {
+        crate::parser::parse_string(0, 0, s).unwrap().eval_no_vars()
+    }
+
+    #[test]
+    fn test_coercion_functions() {
+        assert_eq!(eval_str("to_number('12.5')").unwrap(), DynVal::from(12.5));
+        assert_eq!(eval_str("to_string(1 + 2)").unwrap(), DynVal::from("3"));
+        assert_eq!(eval_str("to_bool('true')").unwrap(), DynVal::from(true));
+        assert_eq!(eval_str("to_bool('false')").unwrap(), DynVal::from(false));
+        assert_eq!(eval_str("to_bool(2)").unwrap(), DynVal::from(true));
+        assert_eq!(eval_str("to_bool(0)").unwrap(), DynVal::from(false));
+        assert!(eval_str("to_bool('maybe')").is_err());
+    }
+
+    #[test]
+    fn test_to_number_error_has_call_span() {
+        let err = eval_str("to_number('abc')").unwrap_err();
+        assert_eq!(err.span(), Span(0, 16, 0));
+    }
+
+    #[test]
+    fn test_aggregate_functions() {
+        assert_eq!(eval_str("count('[1, 2, 3]')").unwrap(), DynVal::from(3));
+        assert_eq!(eval_str("sum('[1, 2, 3.5]')").unwrap(), DynVal::from(6.5));
+        assert_eq!(eval_str("avg('[1, 2, 3]')").unwrap(), DynVal::from(2));
+        assert_eq!(eval_str("min('[4, -2, 3]')").unwrap(), DynVal::from(-2));
+        assert_eq!(eval_str("max('[4, 5, 3]')").unwrap(), DynVal::from(5));
+        assert!(eval_str("sum('[1, true]')").is_err());
+    }
+
+    #[test]
+    fn test_aggregate_functions_empty_array() {
+        assert_eq!(eval_str("count('[]')").unwrap(), DynVal::from(0));
+        assert_eq!(eval_str("sum('[]')").unwrap(), DynVal::from(0));
+        assert!(eval_str("avg('[]')").is_err());
+        assert!(eval_str("min('[]')").is_err());
+        assert!(eval_str("max('[]')").is_err());
+    }
+
+    #[test]
+    fn test_hash_color() {
+        assert_eq!(eval_str("hash_color('workspace-1')").unwrap(), DynVal::from("#5cd6ce"));
+        assert_eq!(eval_str("hash_color('workspace-1')").unwrap(), eval_str("hash_color('workspace-1')").unwrap());
+        assert_ne!(eval_str("hash_color('workspace-1')").unwrap(), eval_str("hash_color('workspace-2')").unwrap());
+        assert_eq!(eval_str("hash_color('workspace-1', 0, 100)").unwrap(), DynVal::from("#ffffff"));
+        assert_eq!(hsl_to_hex(0.0, 100.0, 50.0), "#ff0000");
+        assert_eq!(hsl_to_hex(120.0, 100.0, 50.0), "#00ff00");
+        assert_eq!(hsl_to_hex(240.0, 100.0, 50.0), "#0000ff");
+        assert!(eval_str("hash_color('a', 50)").is_err());
+    }
+
+    #[test]
+    fn test_time_functions() {
+        assert_eq!(eval_str("time_diff(1000, 940)").unwrap(), DynVal::from(60));
+        assert_eq!(eval_str("duration('1h30m')").unwrap(), DynVal::from(5400));
+        assert_eq!(eval_str("duration('2d 1s')").unwrap(), DynVal::from(172801));
+        assert_eq!(eval_str("duration('1.5min')").unwrap(), DynVal::from(90));
+        assert_eq!(eval_str("duration('250ms')").unwrap(), DynVal::from(0.25));
+        assert!(eval_str("duration('')").is_err());
+        assert!(eval_str("duration('1h30')").is_err());
+        assert!(eval_str("duration('3 weeks')").is_err());
+        assert!(eval_str("relative_time(0)").unwrap().as_string().unwrap().ends_with("d ago"));
+        assert_eq!(eval_str("relative_time(1000, 1150)").unwrap(), DynVal::from("2m ago"));
+        assert_eq!(format_relative_time(0.5), "now");
+        assert_eq!(format_relative_time(45.0), "45s ago");
+        assert_eq!(format_relative_time(150.0), "2m ago");
+        assert_eq!(format_relative_time(-180.0), "in 3m");
+        assert_eq!(format_relative_time(-7200.0), "in 2h");
+        assert_eq!(format_relative_time(200000.0), "2d ago");
+    }
+
+    #[test]
+    fn test_tokenizer_functions() {
+        assert_eq!(eval_str("regex_split('a1b22c', '[0-9]+')").unwrap(), DynVal::from(r#"["a","b","c"]"#));
+        assert_eq!(eval_str("lines('foo\nbar baz\n')").unwrap(), DynVal::from(r#"["foo","bar baz"]"#));
+        assert_eq!(eval_str("words(' foo  bar\tbaz ')").unwrap(), DynVal::from(r#"["foo","bar","baz"]"#));
+        assert!(eval_str("regex_split('abc', '(')").is_err());
+    }
+
+    #[test]
+    fn test_boolean_aggregate_functions() {
+        assert_eq!(eval_str("any('[false, true]')").unwrap(), DynVal::from(true));
+        assert_eq!(eval_str("any('[]')").unwrap(), DynVal::from(false));
+        assert_eq!(eval_str("all('[true, false]')").unwrap(), DynVal::from(false));
+        assert_eq!(eval_str("all('[]')").unwrap(), DynVal::from(true));
+        assert!(eval_str("all('[true, 1]')").is_err());
+        assert_eq!(eval_str("matches_any('[\"foo\", \"bar\"]', '^b')").unwrap(), DynVal::from(true));
+        assert_eq!(eval_str("matches_any('[\"foo\", \"bar\"]', '^x')").unwrap(), DynVal::from(false));
+    }
+
+    #[test]
+    fn test_escape_markup() {
+        assert_eq!(eval_str("escape_markup('Tom & Jerry <3')").unwrap(), DynVal::from("Tom &amp; Jerry &lt;3"));
+        assert_eq!(eval_str("escape_markup('plain')").unwrap(), DynVal::from("plain"));
+    }
+}
@@ -46,4 +46,20 @@ mod tests {
             r#"{ "key": "value", 5: 1+2, true: false }"#,
         );
     }
+
+    #[test]
+    fn test_multiline_with_comments() {
+        let parse = |s: &str| super::parse_string(0, 0, s).unwrap().to_string();
+        assert_eq!(
+            parse(
+                r#"
+                foo > 10 ; values above 10 are "high"
+                  ? "high"
+                  : "low" ; everything else
+                "#
+            ),
+            parse(r#"foo > 10 ? "high" : "low""#)
+        );
+        assert_eq!(parse("\"value: ${ foo ; some } comment\n + 1 }\""), parse(r#""value: ${foo + 1}""#));
+    }
 }
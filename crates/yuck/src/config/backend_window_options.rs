@@ -24,6 +24,10 @@ mod backend {
         pub sticky: bool,
         pub window_type: WindowType,
         pub struts: StrutDefinition,
+        /// Instance name of the `WM_CLASS` of the window, if it should not be derived from the window name.
+        pub wm_instance: Option<String>,
+        /// Class name of the `WM_CLASS` of the window, if it should not be derived from the window name.
+        pub wm_class: Option<String>,
     }
 
     impl BackendWindowOptions {
@@ -35,6 +39,8 @@ mod backend {
                 window_type: window_type.unwrap_or_default(),
                 sticky: attrs.primitive_optional("sticky")?.unwrap_or(true),
                 struts: struts.unwrap_or_default(),
+                wm_instance: attrs.primitive_optional("wm-instance")?,
+                wm_class: attrs.primitive_optional("wm-class")?,
             })
         }
     }
@@ -113,12 +119,15 @@ mod backend {
     pub struct BackendWindowOptions {
         pub exclusive: bool,
         pub focusable: bool,
+        /// Layer shell namespace of the window, if it should not be the window name.
+        pub namespace: Option<String>,
     }
     impl BackendWindowOptions {
         pub fn from_attrs(attrs: &mut Attributes) -> AstResult<Self> {
             Ok(Self {
                 exclusive: attrs.primitive_optional("exclusive")?.unwrap_or(false),
                 focusable: attrs.primitive_optional("focusable")?.unwrap_or(false),
+                namespace: attrs.primitive_optional("namespace")?,
             })
         }
     }
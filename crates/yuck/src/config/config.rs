@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -15,7 +15,7 @@ use super::{
     window_definition::WindowDefinition,
 };
 use crate::{
-    config::script_var_definition::{ListenScriptVar, PollScriptVar},
+    config::script_var_definition::{DbusScriptVar, ListenScriptVar, PollScriptVar},
     error::{AstError, AstResult, OptionAstErrorExt},
     parser::{
         ast::Ast,
@@ -31,6 +31,7 @@ pub static TOP_LEVEL_DEFINITION_NAMES: &[&str] = &[
     VarDefinition::ELEMENT_NAME,
     ListenScriptVar::ELEMENT_NAME,
     PollScriptVar::ELEMENT_NAME,
+    DbusScriptVar::ELEMENT_NAME,
     Include::ELEMENT_NAME,
 ];
 
@@ -38,6 +39,8 @@ pub static TOP_LEVEL_DEFINITION_NAMES: &[&str] = &[
 pub struct Include {
     pub path: String,
     pub path_span: Span,
+    /// Namespace given via `:as`, which is prepended to the names of all widgets defined in the included file.
+    pub namespace: Option<String>,
 }
 
 impl FromAstElementContent for Include {
@@ -45,8 +48,10 @@ impl FromAstElementContent for Include {
 
     fn from_tail<I: Iterator<Item = Ast>>(span: Span, mut iter: AstIterator<I>) -> AstResult<Self> {
         let (path_span, path) = iter.expect_literal()?;
+        let mut attrs = iter.expect_key_values()?;
+        let namespace = attrs.ast_optional::<Ast>("as")?.map(|ast| ast.as_symbol()).transpose()?;
         iter.expect_done()?;
-        Ok(Include { path: path.to_string(), path_span })
+        Ok(Include { path: path.to_string(), path_span, namespace })
     }
 }
 
@@ -73,6 +78,9 @@ impl FromAst for TopLevel {
             x if x == ListenScriptVar::ELEMENT_NAME => {
                 Self::ScriptVarDefinition(ScriptVarDefinition::Listen(ListenScriptVar::from_tail(span, iter)?))
             }
+            x if x == DbusScriptVar::ELEMENT_NAME => {
+                Self::ScriptVarDefinition(ScriptVarDefinition::Dbus(DbusScriptVar::from_tail(span, iter)?))
+            }
             x if x == WindowDefinition::ELEMENT_NAME => Self::WindowDefinition(WindowDefinition::from_tail(span, iter)?),
             x => return Err(AstError::UnknownToplevel(sym_span, x.to_string())),
         })
@@ -103,25 +111,62 @@ impl Config {
                 self.window_definitions.insert(x.name.clone(), x);
             }
             TopLevel::Include(include) => {
+                let namespace = include.namespace.clone();
                 let (file_id, toplevels) = files.load_file(PathBuf::from(&include.path)).map_err(|err| match err {
                     FilesError::IoError(_) => AstError::IncludedFileNotFound(include),
                     FilesError::AstError(x) => x,
                 })?;
-                for element in toplevels {
-                    self.append_toplevel(files, TopLevel::from_ast(element)?)?;
+                match namespace {
+                    Some(namespace) => {
+                        let mut included = Self::empty();
+                        for element in toplevels {
+                            included.append_toplevel(files, TopLevel::from_ast(element)?)?;
+                        }
+                        included.qualify_widget_names(&namespace);
+                        self.widget_definitions.extend(included.widget_definitions);
+                        self.window_definitions.extend(included.window_definitions);
+                        self.var_definitions.extend(included.var_definitions);
+                        self.script_vars.extend(included.script_vars);
+                    }
+                    None => {
+                        for element in toplevels {
+                            self.append_toplevel(files, TopLevel::from_ast(element)?)?;
+                        }
+                    }
                 }
             }
         }
         Ok(())
     }
 
-    pub fn generate(files: &mut YuckFiles, elements: Vec<Ast>) -> AstResult<Self> {
-        let mut config = Self {
+    /// Prefix the names of all widgets defined in this config with the given namespace, as in `namespace.name`,
+    /// updating all uses of these widgets within this config accordingly.
+    fn qualify_widget_names(&mut self, namespace: &str) {
+        let names: HashSet<String> = self.widget_definitions.keys().cloned().collect();
+        self.widget_definitions = std::mem::take(&mut self.widget_definitions)
+            .into_values()
+            .map(|mut def| {
+                def.name = format!("{}.{}", namespace, def.name);
+                qualify_widget_uses(&mut def.widget, namespace, &names);
+                (def.name.clone(), def)
+            })
+            .collect();
+        for def in self.window_definitions.values_mut() {
+            qualify_widget_uses(&mut def.widget, namespace, &names);
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
             widget_definitions: HashMap::new(),
             window_definitions: HashMap::new(),
             var_definitions: HashMap::new(),
             script_vars: HashMap::new(),
-        };
+        }
+    }
+
+    pub fn generate(files: &mut YuckFiles, elements: Vec<Ast>) -> AstResult<Self> {
+        let mut config = Self::empty();
         for element in elements {
             config.append_toplevel(files, TopLevel::from_ast(element)?)?;
         }
@@ -136,3 +181,19 @@ impl Config {
         Self::generate(files, top_levels)
     }
 }
+
+/// Prefix all uses of the given widgets within this widget tree with the namespace.
+fn qualify_widget_uses(widget_use: &mut WidgetUse, namespace: &str, names: &HashSet<String>) {
+    match widget_use {
+        WidgetUse::Basic(widget) => {
+            if names.contains(&widget.name) {
+                widget.name = format!("{}.{}", namespace, widget.name);
+            }
+            for child in widget.children.iter_mut() {
+                qualify_widget_uses(child, namespace, names);
+            }
+        }
+        WidgetUse::Loop(widget) => qualify_widget_uses(&mut widget.body, namespace, names),
+        WidgetUse::Children(_) => {}
+    }
+}
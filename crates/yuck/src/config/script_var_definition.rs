@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use simplexpr::{dynval::DynVal, SimplExpr};
 
 use crate::{
+    config::{attributes::AttrError, window_definition::EnumParseError},
+    enum_parse,
     error::{AstError, AstResult, AstResultExt},
     parser::{
         ast::Ast,
@@ -16,6 +18,7 @@ use eww_shared_util::{AttrName, Span, Spanned, VarName};
 pub enum ScriptVarDefinition {
     Poll(PollScriptVar),
     Listen(ListenScriptVar),
+    Dbus(DbusScriptVar),
 }
 
 impl ScriptVarDefinition {
@@ -23,6 +26,7 @@ impl ScriptVarDefinition {
         match self {
             ScriptVarDefinition::Poll(x) => x.name_span,
             ScriptVarDefinition::Listen(x) => x.name_span,
+            ScriptVarDefinition::Dbus(x) => x.name_span,
         }
     }
 
@@ -30,6 +34,7 @@ impl ScriptVarDefinition {
         match self {
             ScriptVarDefinition::Poll(x) => &x.name,
             ScriptVarDefinition::Listen(x) => &x.name,
+            ScriptVarDefinition::Dbus(x) => &x.name,
         }
     }
 
@@ -39,7 +44,19 @@ impl ScriptVarDefinition {
                 VarSource::Shell(span, ..) => Some(span),
                 VarSource::Function(_) => None,
             },
-            ScriptVarDefinition::Listen(x) => Some(x.command_span),
+            ScriptVarDefinition::Listen(x) => match x.command {
+                ListenSource::Shell(_) => Some(x.command_span),
+                ListenSource::Builtin(_) => None,
+            },
+            ScriptVarDefinition::Dbus(_) => None,
+        }
+    }
+
+    pub fn cwd_expr(&self) -> Option<&SimplExpr> {
+        match self {
+            ScriptVarDefinition::Poll(x) => x.cwd.as_ref(),
+            ScriptVarDefinition::Listen(x) => x.cwd.as_ref(),
+            ScriptVarDefinition::Dbus(_) => None,
         }
     }
 }
@@ -52,6 +69,51 @@ pub enum VarSource {
     Function(fn() -> Result<DynVal, Box<dyn std::error::Error + Sync + Send + 'static>>),
 }
 
+/// Where a listen variable gets its values from.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum ListenSource {
+    /// A script whose output lines are the values of the variable.
+    Shell(String),
+    /// A source eww listens to itself, used for magic variables.
+    Builtin(BuiltinListenSource),
+}
+
+/// Sources of magic variables that are kept up to date by eww itself rather than by a script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum BuiltinListenSource {
+    /// The state of the MPRIS media players on the DBus session bus.
+    Mpris,
+}
+
+/// How the output of the script of a variable is interpreted before it is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display, serde::Serialize)]
+pub enum VarFormat {
+    /// Store the output as a string.
+    Raw,
+    /// Parse the output as JSON.
+    Json,
+    /// Collect the lines of the output into a JSON array of strings.
+    Lines,
+}
+
+impl VarFormat {
+    pub fn is_raw(&self) -> bool {
+        *self == VarFormat::Raw
+    }
+}
+
+impl std::str::FromStr for VarFormat {
+    type Err = EnumParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        enum_parse! { "format", s,
+            "raw" => VarFormat::Raw,
+            "json" => VarFormat::Json,
+            "lines" => VarFormat::Lines,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
 pub struct PollScriptVar {
     pub name: VarName,
@@ -60,6 +122,14 @@ pub struct PollScriptVar {
     pub command: VarSource,
     pub initial_value: Option<DynVal>,
     pub interval: std::time::Duration,
+    /// Working directory of the command, evaluated when the variable is started.
+    /// Relative paths are relative to the configuration directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<SimplExpr>,
+    #[serde(skip_serializing_if = "VarFormat::is_raw")]
+    pub format: VarFormat,
+    /// Whether the last value should be stored on disk, to be used as the initial value at the next startup.
+    pub cache: bool,
     pub name_span: Span,
 }
 
@@ -72,6 +142,9 @@ impl FromAstElementContent for PollScriptVar {
             let mut attrs = iter.expect_key_values()?;
             let initial_value = Some(attrs.primitive_optional("initial")?.unwrap_or_else(|| DynVal::from_string(String::new())));
             let interval = attrs.primitive_required::<DynVal, _>("interval")?.as_duration()?;
+            let cwd = attrs.ast_optional::<SimplExpr>("cwd")?;
+            let format = attrs.primitive_optional("format")?.unwrap_or(VarFormat::Raw);
+            let cache = attrs.primitive_optional("cache")?.unwrap_or(false);
             let (script_span, script) = iter.expect_literal()?;
 
             let run_while_expr =
@@ -87,6 +160,9 @@ impl FromAstElementContent for PollScriptVar {
                 command: VarSource::Shell(script_span, script.to_string()),
                 initial_value,
                 interval,
+                cwd,
+                format,
+                cache,
             }
         };
         result.note(r#"Expected format: `(defpoll name :interval "10s" "echo 'a shell script'")`"#)
@@ -96,8 +172,17 @@ impl FromAstElementContent for PollScriptVar {
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
 pub struct ListenScriptVar {
     pub name: VarName,
-    pub command: String,
+    pub command: ListenSource,
     pub initial_value: DynVal,
+    /// Working directory of the command, evaluated when the variable is started.
+    /// Relative paths are relative to the configuration directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<SimplExpr>,
+    #[serde(skip_serializing_if = "VarFormat::is_raw")]
+    pub format: VarFormat,
+    /// Amount of most recent lines the value consists of, required for the `lines` format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
     pub command_span: Span,
     pub name_span: Span,
 }
@@ -109,10 +194,88 @@ impl FromAstElementContent for ListenScriptVar {
             let (name_span, name) = iter.expect_symbol()?;
             let mut attrs = iter.expect_key_values()?;
             let initial_value = attrs.primitive_optional("initial")?.unwrap_or_else(|| DynVal::from_string(String::new()));
+            let cwd = attrs.ast_optional::<SimplExpr>("cwd")?;
+            let format = attrs.primitive_optional("format")?.unwrap_or(VarFormat::Raw);
+            let max_lines = attrs.primitive_optional("max-lines")?;
+            if format == VarFormat::Lines && max_lines.is_none() {
+                Err(AstError::from(AttrError::MissingRequiredAttr(span, AttrName("max-lines".to_string()))))?;
+            }
             let (command_span, script) = iter.expect_literal()?;
             iter.expect_done()?;
-            Self { name_span, name: VarName(name), command: script.to_string(), initial_value, command_span }
+            Self {
+                name_span,
+                name: VarName(name),
+                command: ListenSource::Shell(script.to_string()),
+                initial_value,
+                cwd,
+                format,
+                max_lines,
+                command_span,
+            }
         };
         result.note(r#"Expected format: `(deflisten name :initial "0" "tail -f /tmp/example")`"#)
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display, serde::Serialize)]
+pub enum DbusBus {
+    Session,
+    System,
+}
+
+impl std::str::FromStr for DbusBus {
+    type Err = EnumParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        enum_parse! { "DBus bus", s,
+            "session" => DbusBus::Session,
+            "system" => DbusBus::System,
+        }
+    }
+}
+
+/// The member of a DBus interface a [`DbusScriptVar`] reads its value from.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum DbusMember {
+    Property(String),
+    Signal(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct DbusScriptVar {
+    pub name: VarName,
+    pub bus: DbusBus,
+    pub service: String,
+    pub path: String,
+    pub interface: String,
+    pub member: DbusMember,
+    pub initial_value: DynVal,
+    pub name_span: Span,
+}
+
+impl FromAstElementContent for DbusScriptVar {
+    const ELEMENT_NAME: &'static str = "defdbus";
+
+    fn from_tail<I: Iterator<Item = Ast>>(span: Span, mut iter: AstIterator<I>) -> AstResult<Self> {
+        let result: AstResult<_> = try {
+            let (name_span, name) = iter.expect_symbol()?;
+            let mut attrs = iter.expect_key_values()?;
+            let initial_value = attrs.primitive_optional("initial")?.unwrap_or_else(|| DynVal::from_string(String::new()));
+            let bus = attrs.primitive_optional("bus")?.unwrap_or(DbusBus::Session);
+            let service = attrs.primitive_required("service")?;
+            let path = attrs.primitive_required("path")?;
+            let interface = attrs.primitive_required("interface")?;
+            let member = match (attrs.primitive_optional("property")?, attrs.primitive_optional("signal")?) {
+                (Some(property), None) => DbusMember::Property(property),
+                (None, Some(signal)) => DbusMember::Signal(signal),
+                (None, None) => Err(AttrError::MissingRequiredAttr(span, AttrName("property".to_string())))?,
+                (Some(_), Some(_)) => Err(AstError::Other(span, "Only one of `:property` and `:signal` may be given".into()))?,
+            };
+            iter.expect_done()?;
+            Self { name_span, name: VarName(name), bus, service, path, interface, member, initial_value }
+        };
+        result.note(
+            r#"Expected format: `(defdbus name :bus "system" :service "org.example" :path "/org/example" :interface "org.example.Iface" :property "Prop")`"#,
+        )
+    }
+}
@@ -1,7 +1,14 @@
 use crate::{
-    config::config::Config,
+    config::{
+        config::Config,
+        script_var_definition::{DbusBus, DbusMember, DbusScriptVar, ScriptVarDefinition},
+        validate,
+        widget_use::WidgetUse,
+    },
     parser::{self, ast::Ast, from_ast::FromAst, lexer::Lexer},
 };
+use eww_shared_util::VarName;
+use simplexpr::dynval::DynVal;
 
 use super::file_provider::YuckFiles;
 
@@ -28,3 +35,238 @@ fn test_config() {
         insta::assert_ron_snapshot!(config.unwrap());
     });
 }
+
+fn parse_config(input: &str) -> Config {
+    let mut files = YuckFiles::new();
+    let (_, asts) = files.load_str("config.yuck".to_string(), input.to_string()).unwrap();
+    Config::generate(&mut files, asts).unwrap()
+}
+
+#[test]
+fn test_derived_var_definition() {
+    let config = parse_config(
+        r#"
+        (defvar plain "bla")
+        (defvar color "red")
+        (defvar theme :value {{"fg": color, "bg": "black"}})
+    "#,
+    );
+    let plain = &config.var_definitions[&VarName::from("plain")];
+    assert_eq!(plain.initial_value, DynVal::from("bla"));
+    assert_eq!(plain.value_expr, None);
+
+    let theme = &config.var_definitions[&VarName::from("theme")];
+    let value_expr = theme.value_expr.as_ref().unwrap();
+    assert_eq!(value_expr.collect_var_refs(), vec![VarName::from("color")]);
+    let values = maplit::hashmap! { VarName::from("color") => DynVal::from("red") };
+    assert_eq!(value_expr.eval(&values).unwrap(), DynVal::from(r#"{"bg":"black","fg":"red"}"#));
+
+    let mut files = YuckFiles::new();
+    let (_, asts) = files.load_str("config.yuck".to_string(), r#"(defvar theme :value {1} "initial")"#.to_string()).unwrap();
+    assert!(Config::generate(&mut files, asts).is_err());
+}
+
+#[test]
+fn test_derived_var_with_unknown_variable_fails_validation() {
+    let config = parse_config(r#"(defvar theme :value {{"fg": colour}})"#);
+    assert!(validate::validate(&config, Vec::new(), &[]).is_err());
+    let config = parse_config(r#"(defvar colour "red") (defvar theme :value {{"fg": colour}})"#);
+    assert!(validate::validate(&config, Vec::new(), &[]).is_ok());
+}
+
+#[test]
+fn test_script_var_cwd_expression() {
+    let config = parse_config(r#"(defpoll status :interval "10s" :cwd {EWW_CONFIG_DIR + "/scripts"} "./status.sh")"#);
+    let cwd_expr = config.script_vars[&VarName::from("status")].cwd_expr().unwrap();
+    assert_eq!(cwd_expr.collect_var_refs(), vec![VarName::from("EWW_CONFIG_DIR")]);
+    assert!(validate::validate(&config, Vec::new(), &[]).is_err());
+    assert!(validate::validate(&config, vec![VarName::from("EWW_CONFIG_DIR")], &[]).is_ok());
+
+    let config = parse_config(r#"(deflisten status :cwd "scripts" "./status.sh")"#);
+    let cwd_expr = config.script_vars[&VarName::from("status")].cwd_expr().unwrap();
+    assert_eq!(cwd_expr.eval_no_vars().unwrap(), DynVal::from("scripts"));
+}
+
+#[test]
+fn test_child_variable_is_in_scope_of_children() {
+    let tree = validate::ChildVariable { widget_name: "tree", name_attr: "var", default_name: "node" };
+    let config = parse_config(r#"(defwidget files [] (tree :nodes "[]" (label :text {node.name})))"#);
+    assert!(validate::validate(&config, Vec::new(), &[]).is_err());
+    assert!(validate::validate(&config, Vec::new(), &[tree]).is_ok());
+
+    let config = parse_config(r#"(defwidget files [] (tree :nodes "[]" :var "file" (label :text {file.name})))"#);
+    assert!(validate::validate(&config, Vec::new(), &[tree]).is_ok());
+    let config = parse_config(r#"(defwidget files [] (tree :nodes "[]" :var "file" (label :text {node.name})))"#);
+    assert!(validate::validate(&config, Vec::new(), &[tree]).is_err());
+}
+
+#[test]
+fn test_namespaced_include() {
+    let dir = std::env::temp_dir().join(format!("yuck_include_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let included_path = dir.join("bar.yuck");
+    std::fs::write(
+        &included_path,
+        r#"
+        (defwidget clock [] (label :text "12:00"))
+        (defwidget bar [] (box (clock) (other)))
+        (defwindow bar-window (bar))
+    "#,
+    )
+    .unwrap();
+    let config = parse_config(&format!(
+        r#"
+        (include "{path}" :as bar)
+        (defwidget other [] (label :text "other"))
+        (defwindow main (bar.clock))
+    "#,
+        path = included_path.display()
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut widget_names: Vec<_> = config.widget_definitions.keys().cloned().collect();
+    widget_names.sort();
+    assert_eq!(widget_names, vec!["bar.bar", "bar.clock", "other"]);
+    // uses of widgets from the included file are qualified, uses of widgets defined elsewhere are kept as they are
+    let bar_children: Vec<_> = match &config.widget_definitions["bar.bar"].widget {
+        WidgetUse::Basic(widget) => widget.children.iter().map(widget_use_name).collect(),
+        _ => panic!("Expected a basic widget"),
+    };
+    assert_eq!(bar_children, vec!["bar.clock", "other"]);
+    assert_eq!(widget_use_name(&config.window_definitions["bar-window"].widget), "bar.bar");
+    assert_eq!(widget_use_name(&config.window_definitions["main"].widget), "bar.clock");
+    assert!(validate::validate(&config, Vec::new(), &[]).is_ok());
+}
+
+#[test]
+fn test_include_without_namespace() {
+    let dir = std::env::temp_dir().join(format!("yuck_include_plain_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let included_path = dir.join("bar.yuck");
+    std::fs::write(&included_path, r#"(defwidget clock [] (label :text "12:00"))"#).unwrap();
+    let config = parse_config(&format!(r#"(include "{}") (defwindow main (clock))"#, included_path.display()));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(config.widget_definitions.contains_key("clock"));
+    assert_eq!(widget_use_name(&config.window_definitions["main"].widget), "clock");
+}
+
+#[test]
+fn test_listen_var_lines_format_requires_max_lines() {
+    let config = parse_config(r#"(deflisten recent :format "lines" :max-lines 5 "tail -F /tmp/foo")"#);
+    match &config.script_vars[&VarName::from("recent")] {
+        ScriptVarDefinition::Listen(var) => assert_eq!(var.max_lines, Some(5)),
+        _ => panic!("Expected a listen variable"),
+    }
+
+    let mut files = YuckFiles::new();
+    let (_, asts) = files
+        .load_str("config.yuck".to_string(), r#"(deflisten recent :format "lines" "tail -F /tmp/foo")"#.to_string())
+        .unwrap();
+    assert!(Config::generate(&mut files, asts).is_err());
+}
+
+fn parse_dbus_var(input: &str) -> Result<DbusScriptVar, String> {
+    let mut files = YuckFiles::new();
+    let (_, asts) = files.load_str("config.yuck".to_string(), input.to_string()).unwrap();
+    let config = Config::generate(&mut files, asts).map_err(|err| format!("{:?}", err))?;
+    match config.script_vars.into_values().next() {
+        Some(ScriptVarDefinition::Dbus(var)) => Ok(var),
+        other => panic!("Expected a dbus variable, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_dbus_property_var() {
+    let var = parse_dbus_var(
+        r#"
+        (defdbus battery :bus "system"
+                         :service "org.freedesktop.UPower"
+                         :path "/org/freedesktop/UPower/devices/DisplayDevice"
+                         :interface "org.freedesktop.UPower.Device"
+                         :property "State"
+                         :initial "0")
+    "#,
+    )
+    .unwrap();
+    assert_eq!(var.name, VarName::from("battery"));
+    assert_eq!(var.bus, DbusBus::System);
+    assert_eq!(var.service, "org.freedesktop.UPower");
+    assert_eq!(var.path, "/org/freedesktop/UPower/devices/DisplayDevice");
+    assert_eq!(var.interface, "org.freedesktop.UPower.Device");
+    assert_eq!(var.member, DbusMember::Property("State".to_string()));
+    assert_eq!(var.initial_value, DynVal::from("0"));
+}
+
+#[test]
+fn test_dbus_signal_var() {
+    let var = parse_dbus_var(
+        r#"(defdbus notified :service "org.example" :path "/org/example" :interface "org.example.Iface" :signal "Notified")"#,
+    )
+    .unwrap();
+    assert_eq!(var.bus, DbusBus::Session);
+    assert_eq!(var.member, DbusMember::Signal("Notified".to_string()));
+    assert_eq!(var.initial_value, DynVal::from(""));
+}
+
+#[test]
+fn test_invalid_dbus_var() {
+    // neither a property nor a signal
+    assert!(
+        parse_dbus_var(r#"(defdbus foo :service "org.example" :path "/org/example" :interface "org.example.Iface")"#).is_err()
+    );
+    // both a property and a signal
+    assert!(parse_dbus_var(
+        r#"(defdbus foo :service "org.example" :path "/org/example" :interface "org.example.Iface" :property "A" :signal "B")"#
+    )
+    .is_err());
+    // missing service
+    assert!(parse_dbus_var(r#"(defdbus foo :path "/org/example" :interface "org.example.Iface" :property "A")"#).is_err());
+    // unknown bus
+    assert!(parse_dbus_var(
+        r#"(defdbus foo :bus "user" :service "org.example" :path "/org/example" :interface "org.example.Iface" :property "A")"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_for_with_key() {
+    let config = parse_config(
+        r#"
+        (defwidget list [items]
+            (box (for item in items :key {item.id}
+                (label :text {item.name}))))
+    "#,
+    );
+    let loop_use = match &config.widget_definitions["list"].widget {
+        WidgetUse::Basic(widget) => match &widget.children[..] {
+            [WidgetUse::Loop(loop_use)] => loop_use,
+            _ => panic!("Expected a single loop"),
+        },
+        _ => panic!("Expected a basic widget"),
+    };
+    assert_eq!(loop_use.element_name, VarName::from("item"));
+    assert_eq!(loop_use.key_expr.as_ref().unwrap().collect_var_refs(), vec![VarName::from("item")]);
+    assert_eq!(widget_use_name(&loop_use.body), "label");
+
+    let config = parse_config(r#"(defwidget list [items] (box (for item in items (label :text item))))"#);
+    match &config.widget_definitions["list"].widget {
+        WidgetUse::Basic(widget) => {
+            assert!(matches!(&widget.children[..], [WidgetUse::Loop(loop_use)] if loop_use.key_expr.is_none()))
+        }
+        _ => panic!("Expected a basic widget"),
+    }
+
+    let mut files = YuckFiles::new();
+    let (_, asts) = files
+        .load_str("config.yuck".to_string(), r#"(defwidget list [items] (box (for item in items :id "a" item)))"#.to_string())
+        .unwrap();
+    assert!(Config::generate(&mut files, asts).is_err());
+}
+
+fn widget_use_name(widget_use: &WidgetUse) -> &str {
+    match widget_use {
+        WidgetUse::Basic(widget) => &widget.name,
+        _ => panic!("Expected a basic widget"),
+    }
+}
@@ -34,6 +34,29 @@ pub enum ValidationError {
     },
 }
 
+/// A variable that a builtin widget binds within the scope of its children, such as the node rendered by a `tree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildVariable {
+    pub widget_name: &'static str,
+    /// The attribute that may be used to choose a different name for the variable. Its value must be a literal.
+    pub name_attr: &'static str,
+    pub default_name: &'static str,
+}
+
+impl ChildVariable {
+    /// Get the name the variable is bound as in the children of the given use of [`Self::widget_name`].
+    pub fn name_in(&self, widget: &BasicWidgetUse) -> VarName {
+        widget
+            .attrs
+            .attrs
+            .get(self.name_attr)
+            .and_then(|entry| entry.value.as_simplexpr().ok())
+            .and_then(|expr| expr.eval_no_vars().ok())
+            .map(|value| VarName(value.0))
+            .unwrap_or_else(|| VarName::from(self.default_name))
+    }
+}
+
 impl Spanned for ValidationError {
     fn span(&self) -> Span {
         match self {
@@ -45,23 +68,53 @@ impl Spanned for ValidationError {
     }
 }
 
-pub fn validate(config: &Config, additional_globals: Vec<VarName>) -> Result<(), ValidationError> {
+pub fn validate(
+    config: &Config,
+    additional_globals: Vec<VarName>,
+    child_variables: &[ChildVariable],
+) -> Result<(), ValidationError> {
     let var_names = std::iter::empty()
         .chain(additional_globals.iter().cloned())
         .chain(config.script_vars.keys().cloned())
         .chain(config.var_definitions.keys().cloned())
         .collect();
+    for var in config.var_definitions.values() {
+        if let Some(value_expr) = &var.value_expr {
+            validate_variables_in_expr(&var_names, value_expr, false)?;
+        }
+    }
+    for var in config.script_vars.values() {
+        if let Some(cwd_expr) = var.cwd_expr() {
+            validate_variables_in_expr(&var_names, cwd_expr, false)?;
+        }
+    }
     for window in config.window_definitions.values() {
-        validate_variables_in_widget_use(&config.widget_definitions, &var_names, &window.widget, false)?;
+        if let Some(monitor_expr) = &window.monitor_expr {
+            validate_variables_in_expr(&var_names, monitor_expr, false)?;
+        }
+        validate_variables_in_widget_use(&config.widget_definitions, child_variables, &var_names, &window.widget, false)?;
     }
     for def in config.widget_definitions.values() {
-        validate_widget_definition(&config.widget_definitions, &var_names, def)?;
+        validate_widget_definition(&config.widget_definitions, child_variables, &var_names, def)?;
     }
     Ok(())
 }
 
+pub fn validate_variables_in_expr(
+    variables: &HashSet<VarName>,
+    expr: &SimplExpr,
+    is_in_definition: bool,
+) -> Result<(), ValidationError> {
+    let unknown_var = expr.var_refs_with_span().into_iter().find(|(_, var_ref)| !variables.contains(*var_ref));
+    match unknown_var {
+        Some((span, var)) => Err(ValidationError::UnknownVariable { span, name: var.clone(), in_definition: is_in_definition }),
+        None => Ok(()),
+    }
+}
+
 pub fn validate_widget_definition(
     other_defs: &HashMap<String, WidgetDefinition>,
+    child_variables: &[ChildVariable],
     globals: &HashSet<VarName>,
     def: &WidgetDefinition,
 ) -> Result<(), ValidationError> {
@@ -70,11 +123,12 @@ pub fn validate_widget_definition(
         variables_in_scope.insert(VarName(arg.name.to_string()));
     }
 
-    validate_variables_in_widget_use(other_defs, &variables_in_scope, &def.widget, true)
+    validate_variables_in_widget_use(other_defs, child_variables, &variables_in_scope, &def.widget, true)
 }
 
 pub fn validate_variables_in_widget_use(
     defs: &HashMap<String, WidgetDefinition>,
+    child_variables: &[ChildVariable],
     variables: &HashSet<VarName>,
     widget: &WidgetUse,
     is_in_definition: bool,
@@ -108,15 +162,23 @@ pub fn validate_variables_in_widget_use(
             return Err(ValidationError::UnknownVariable { span, name: var, in_definition: is_in_definition });
         }
 
+        let bound_variables: HashSet<VarName>;
+        let children_variables = match child_variables.iter().find(|var| var.widget_name == widget.name) {
+            Some(child_variable) => {
+                bound_variables = variables.iter().cloned().chain(std::iter::once(child_variable.name_in(widget))).collect();
+                &bound_variables
+            }
+            None => variables,
+        };
         for child in widget.children.iter() {
-            let _ = validate_variables_in_widget_use(defs, variables, child, is_in_definition)?;
+            let _ = validate_variables_in_widget_use(defs, child_variables, children_variables, child, is_in_definition)?;
         }
     } else if let WidgetUse::Loop(widget) = widget {
         let unknown_var = widget
             .elements_expr
             .var_refs_with_span()
-            .iter()
-            .cloned()
+            .into_iter()
+            .chain(widget.key_expr.iter().flat_map(|key_expr| key_expr.var_refs_with_span()))
             .map(|(span, var_ref)| (span, var_ref.clone()))
             .find(|(_, var_ref)| var_ref != &widget.element_name && !variables.contains(var_ref));
         if let Some((span, var)) = unknown_var {
@@ -124,7 +186,7 @@ pub fn validate_variables_in_widget_use(
         }
         let mut variables = variables.clone();
         variables.insert(widget.element_name.clone());
-        let _ = validate_variables_in_widget_use(defs, &variables, &widget.body, is_in_definition)?;
+        let _ = validate_variables_in_widget_use(defs, child_variables, &variables, &widget.body, is_in_definition)?;
     }
 
     Ok(())
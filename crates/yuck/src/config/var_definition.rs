@@ -16,6 +16,10 @@ use eww_shared_util::{AttrName, Span, VarName};
 pub struct VarDefinition {
     pub name: VarName,
     pub initial_value: DynVal,
+    /// Expression the value of this variable is derived from, given via `:value`.
+    /// The variable is recomputed whenever any of the variables referenced in this expression change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_expr: Option<SimplExpr>,
     pub span: Span,
 }
 
@@ -25,10 +29,15 @@ impl FromAstElementContent for VarDefinition {
     fn from_tail<I: Iterator<Item = Ast>>(span: Span, mut iter: AstIterator<I>) -> AstResult<Self> {
         let result: AstResult<_> = try {
             let (_, name) = iter.expect_symbol()?;
-            let (_, initial_value) = iter.expect_literal()?;
+            let mut attrs = iter.expect_key_values()?;
+            let value_expr: Option<SimplExpr> = attrs.ast_optional("value")?;
+            let initial_value = match value_expr {
+                Some(_) => DynVal::from_string(String::new()),
+                None => iter.expect_literal()?.1,
+            };
             iter.expect_done()?;
-            Self { name: VarName(name), initial_value, span }
+            Self { name: VarName(name), initial_value, value_expr, span }
         };
-        result.note(r#"Expected format: `(defvar name "initial-value")`"#)
+        result.note(r#"Expected format: `(defvar name "initial-value")` or `(defvar name :value {some_expression})`"#)
     }
 }
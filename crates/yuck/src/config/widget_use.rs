@@ -27,6 +27,8 @@ pub struct LoopWidgetUse {
     pub element_name: VarName,
     pub elements_expr: SimplExpr,
     pub elements_expr_span: Span,
+    /// Expression identifying an element, given via `:key`. Children of elements whose key stays the same are kept across updates.
+    pub key_expr: Option<SimplExpr>,
     pub body: Box<WidgetUse>,
     pub span: Span,
 }
@@ -77,11 +79,20 @@ impl FromAstElementContent for LoopWidgetUse {
             return Err(AstError::FormFormatError(FormFormatError::ExpectedInInForLoop(in_string_span, in_string)));
         }
         let (elements_span, elements_expr) = iter.expect_simplexpr()?;
+        let mut attrs = iter.expect_key_values()?;
+        let key_expr = attrs.ast_optional("key")?;
+        if let Some((attr_name, entry)) = attrs.attrs.iter().next() {
+            return Err(AstError::Other(
+                entry.key_span,
+                format!("Unknown attribute `{}`, `for` only supports `:key`", attr_name).into(),
+            ));
+        }
         let body = iter.expect_any().note("Expected a loop body").and_then(WidgetUse::from_ast)?;
         iter.expect_done()?;
         Ok(Self {
             element_name: VarName(element_name),
             elements_expr,
+            key_expr,
             body: Box::new(body),
             span,
             elements_expr_span: elements_span,
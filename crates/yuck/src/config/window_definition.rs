@@ -3,6 +3,7 @@ use std::{collections::HashMap, fmt::Display, str::FromStr};
 use simplexpr::{dynval::DynVal, SimplExpr};
 
 use crate::{
+    config::attributes::AttrError,
     error::{AstError, AstResult},
     parser::{
         ast::Ast,
@@ -11,7 +12,7 @@ use crate::{
     },
     value::NumWithUnit,
 };
-use eww_shared_util::{AttrName, Span, VarName};
+use eww_shared_util::{AttrName, Span, Spanned, VarName};
 
 use super::{backend_window_options::BackendWindowOptions, widget_use::WidgetUse, window_geometry::WindowGeometry};
 
@@ -21,9 +22,24 @@ pub struct WindowDefinition {
     pub geometry: Option<WindowGeometry>,
     pub stacking: WindowStacking,
     pub monitor_number: Option<i32>,
+    /// Expression the monitor is derived from, if the monitor depends on variables.
+    /// The window is moved to a different monitor whenever the value of this expression changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_expr: Option<SimplExpr>,
     pub widget: WidgetUse,
     pub resizable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<NumWithUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<NumWithUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<NumWithUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<NumWithUnit>,
     pub backend_options: BackendWindowOptions,
+    /// Animation played when the window is closed, before it is destroyed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_animation: Option<WindowCloseAnimation>,
 }
 
 impl FromAstElementContent for WindowDefinition {
@@ -32,14 +48,50 @@ impl FromAstElementContent for WindowDefinition {
     fn from_tail<I: Iterator<Item = Ast>>(span: Span, mut iter: AstIterator<I>) -> AstResult<Self> {
         let (_, name) = iter.expect_symbol()?;
         let mut attrs = iter.expect_key_values()?;
-        let monitor_number = attrs.primitive_optional("monitor")?;
+        let (monitor_number, monitor_expr) = match attrs.ast_optional::<SimplExpr>("monitor")? {
+            Some(expr) if expr.collect_var_refs().is_empty() => {
+                let monitor = expr
+                    .eval_no_vars()
+                    .map_err(|err| AttrError::EvaluationError(expr.span(), err))?
+                    .read_as()
+                    .map_err(|e| AttrError::Other(expr.span(), Box::new(e)))?;
+                (Some(monitor), None)
+            }
+            Some(expr) => (None, Some(expr)),
+            None => (None, None),
+        };
         let resizable = attrs.primitive_optional("resizable")?.unwrap_or(true);
+        let min_width = attrs.primitive_optional("min-width")?;
+        let min_height = attrs.primitive_optional("min-height")?;
+        let max_width = attrs.primitive_optional("max-width")?;
+        let max_height = attrs.primitive_optional("max-height")?;
         let stacking = attrs.primitive_optional("stacking")?.unwrap_or(WindowStacking::Foreground);
         let geometry = attrs.ast_optional("geometry")?;
         let backend_options = BackendWindowOptions::from_attrs(&mut attrs)?;
+        let close_duration = attrs.primitive_optional::<DynVal, _>("close-duration")?.map(|x| x.as_duration()).transpose()?;
+        let close_animation = match attrs.primitive_optional("close-transition")? {
+            Some(WindowCloseTransition::None) | None => None,
+            Some(transition) => {
+                Some(WindowCloseAnimation { transition, duration: close_duration.unwrap_or(DEFAULT_CLOSE_ANIMATION_DURATION) })
+            }
+        };
         let widget = iter.expect_any().and_then(WidgetUse::from_ast)?;
         iter.expect_done()?;
-        Ok(Self { name, monitor_number, resizable, widget, stacking, geometry, backend_options })
+        Ok(Self {
+            name,
+            monitor_number,
+            monitor_expr,
+            resizable,
+            min_width,
+            min_height,
+            max_width,
+            max_height,
+            widget,
+            stacking,
+            geometry,
+            backend_options,
+            close_animation,
+        })
     }
 }
 
@@ -77,6 +129,33 @@ macro_rules! enum_parse {
     };
 }
 
+/// Duration of the close animation of a window, if none is given explicitly.
+const DEFAULT_CLOSE_ANIMATION_DURATION: std::time::Duration = std::time::Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct WindowCloseAnimation {
+    pub transition: WindowCloseTransition,
+    pub duration: std::time::Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display, smart_default::SmartDefault, serde::Serialize)]
+pub enum WindowCloseTransition {
+    #[default]
+    None,
+    Fade,
+}
+
+impl std::str::FromStr for WindowCloseTransition {
+    type Err = EnumParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        enum_parse! { "WindowCloseTransition", s,
+            "none" => WindowCloseTransition::None,
+            "fade" | "crossfade" => WindowCloseTransition::Fade,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display, smart_default::SmartDefault, serde::Serialize)]
 pub enum WindowStacking {
     #[default]
@@ -78,3 +78,18 @@ fn test() {
         "\"h\\\"i\""
     );
 }
+
+#[test]
+fn test_multiline_simplexpr_with_comments() {
+    let parse_expr = |s: &str| parse_string(0, s).unwrap().as_simplexpr().unwrap().to_string();
+    assert_eq!(
+        parse_expr(
+            r#"{
+                foo > 10 ; a comment may contain } and "
+                  ? "high"
+                  : "low"
+            }"#
+        ),
+        parse_expr(r#"{foo > 10 ? "high" : "low"}"#)
+    );
+}